@@ -62,7 +62,7 @@ async fn run_in_child_process(child_no: u32) {
     span.set_attribute(KeyValue::new("child_no", child_no as i64));
     span.add_event("leaf fn", vec![]);
     sleep(Duration::from_millis(50)).await;
-    if (child_no + 1) % 4 == 0 {
+    if (child_no + 1).is_multiple_of(4) {
         let error: Box<dyn std::error::Error> = "An error".into();
         span.record_error(error.as_ref());
     }
@@ -7,9 +7,9 @@ use chrono::{DateTime, SecondsFormat, Utc};
 use opentelemetry::trace::{Link, Status};
 #[cfg(any(feature = "trace", feature = "logs"))]
 use opentelemetry::KeyValue;
-use opentelemetry::Value;
 #[cfg(feature = "logs")]
 use opentelemetry::{logs::AnyValue, Key};
+use opentelemetry::{Array, Value};
 #[cfg(any(feature = "trace", feature = "logs"))]
 use opentelemetry_sdk::Resource;
 #[cfg(any(feature = "trace", feature = "logs"))]
@@ -144,11 +144,69 @@ impl<'a> From<&'a (Key, AnyValue)> for AttrKeyValue<'a> {
 
 pub(crate) trait AttrValue {
     fn as_str(&self) -> Cow<'_, str>;
+
+    /// Numeric value, if the value is an integer or a floating point number.
+    #[cfg(any(feature = "trace", feature = "logs"))]
+    fn as_f64(&self) -> Option<f64>;
+
+    /// Values of a list, if the value is a list.
+    #[cfg(any(feature = "trace", feature = "logs"))]
+    fn as_str_list(&self) -> Option<Vec<Cow<'_, str>>>;
 }
 
 impl AttrValue for Value {
     fn as_str(&self) -> Cow<'_, str> {
-        self.as_str()
+        match self {
+            Value::Array(array) => array_to_json(array).to_string().into(),
+            _ => self.as_str(),
+        }
+    }
+
+    #[cfg(any(feature = "trace", feature = "logs"))]
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::I64(v) => Some(*v as f64),
+            Value::F64(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    #[cfg(any(feature = "trace", feature = "logs"))]
+    fn as_str_list(&self) -> Option<Vec<Cow<'_, str>>> {
+        match self {
+            Value::Array(Array::String(v)) => Some(v.iter().map(|x| x.as_str().into()).collect()),
+            _ => None,
+        }
+    }
+}
+
+/// JSON representation of an array, so it can be parsed in queries with `parse_json`.
+fn array_to_json(array: &Array) -> serde_json::Value {
+    match array {
+        Array::Bool(v) => v.as_slice().into(),
+        Array::I64(v) => v.as_slice().into(),
+        Array::F64(v) => v.as_slice().into(),
+        Array::String(v) => v.iter().map(|x| x.as_str()).collect(),
+        _ => serde_json::Value::Null,
+    }
+}
+
+/// JSON representation of a log value, so it can be parsed in queries with `parse_json`.
+#[cfg(feature = "logs")]
+pub(crate) fn any_value_to_json(value: &AnyValue) -> serde_json::Value {
+    match value {
+        AnyValue::Int(v) => (*v).into(),
+        AnyValue::Double(v) => (*v).into(),
+        AnyValue::String(v) => v.as_str().into(),
+        AnyValue::Boolean(v) => (*v).into(),
+        AnyValue::Bytes(v) => v.as_slice().into(),
+        AnyValue::ListAny(v) => v.iter().map(any_value_to_json).collect(),
+        AnyValue::Map(v) => serde_json::Value::Object(
+            v.iter()
+                .map(|(k, v)| (k.to_string(), any_value_to_json(v)))
+                .collect(),
+        ),
+        _ => serde_json::Value::Null,
     }
 }
 
@@ -160,57 +218,34 @@ impl AttrValue for AnyValue {
             AnyValue::Double(v) => format!("{}", v).into(),
             AnyValue::String(v) => Cow::Borrowed(v.as_str()),
             AnyValue::Boolean(v) => format!("{}", v).into(),
-            AnyValue::Bytes(bytes) => {
-                let mut s = String::new();
-                s.push('[');
-                for &b in bytes.as_ref() {
-                    s.push_str(&format!("{}", b));
-                    s.push(',');
-                }
-                if !bytes.is_empty() {
-                    s.pop(); // remove trailing comma
-                }
-                s.push(']');
-                s.into()
-            }
-            AnyValue::ListAny(list) => {
-                let mut s = String::new();
-                s.push('[');
-                for v in list.as_ref() {
-                    s.push_str(v.as_str().as_ref());
-                    s.push(',');
-                }
-                if !list.is_empty() {
-                    s.pop(); // remove trailing comma
-                }
-                s.push(']');
-                s.into()
-            }
-            AnyValue::Map(map) => {
-                let mut s = String::new();
-                s.push('{');
-                for (k, v) in map.as_ref() {
-                    s.push_str(k.as_str());
-                    s.push(':');
-                    s.push_str(v.as_str().as_ref());
-                    s.push(',');
-                }
-                if !map.is_empty() {
-                    s.pop(); // remove trailing comma
-                }
-                s.push('}');
-                s.into()
+            AnyValue::Bytes(_) | AnyValue::ListAny(_) | AnyValue::Map(_) => {
+                any_value_to_json(self).to_string().into()
             }
             _ => format!("{:?}", self).into(),
         }
     }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            AnyValue::Int(v) => Some(*v as f64),
+            AnyValue::Double(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    fn as_str_list(&self) -> Option<Vec<Cow<'_, str>>> {
+        match self {
+            AnyValue::ListAny(list) => Some(list.iter().map(|x| x.as_str()).collect()),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::models::MS_LINKS_MAX_LEN;
-    use opentelemetry::trace::SpanContext;
+    use opentelemetry::{trace::SpanContext, StringValue};
     use test_case::test_case;
 
     #[test_case(Duration::from_micros(123456789123), "1.10:17:36.789123" ; "all")]
@@ -281,10 +316,20 @@ mod tests {
     #[test_case(AnyValue::Bytes(Box::default()), "[]" ; "empty bytes")]
     #[test_case(AnyValue::Bytes(Box::new(vec![1, 2, 3])), "[1,2,3]" ; "bytes")]
     #[test_case(AnyValue::ListAny(Box::default()), "[]" ; "empty list")]
-    #[test_case(AnyValue::ListAny(Box::new(vec![1.into(), "test".into()])), "[1,test]" ; "list")]
+    #[test_case(AnyValue::ListAny(Box::new(vec![1.into(), "test".into()])), r#"[1,"test"]"# ; "list")]
     #[test_case(AnyValue::Map(Box::new([].into())), "{}" ; "empty map")]
-    #[test_case(AnyValue::Map(Box::new([("k1".into(), "test".into())].into())), "{k1:test}" ; "map")]
+    #[test_case(AnyValue::Map(Box::new([("k1".into(), "test".into())].into())), r#"{"k1":"test"}"# ; "map")]
+    #[test_case(AnyValue::Map(Box::new([("k1".into(), AnyValue::ListAny(Box::new(vec![true.into()])))].into())), r#"{"k1":[true]}"# ; "nested")]
     fn any_value_as_str(v: AnyValue, expected: &'static str) {
         assert_eq!(expected.to_string(), (&v as &dyn AttrValue).as_str());
     }
+
+    #[test_case(Value::Array(vec![1, 2].into()), "[1,2]" ; "int array")]
+    #[test_case(Value::Array(vec![true].into()), "[true]" ; "bool array")]
+    #[test_case(Value::Array(vec![1.5].into()), "[1.5]" ; "float array")]
+    #[test_case(Value::Array(vec![StringValue::from("a\"b")].into()), r#"["a\"b"]"# ; "string array")]
+    #[test_case(Value::String("test".into()), "test" ; "string")]
+    fn value_as_str(v: Value, expected: &'static str) {
+        assert_eq!(expected.to_string(), (&v as &dyn AttrValue).as_str());
+    }
 }
@@ -0,0 +1,391 @@
+use crate::metrics::{ExponentialBuckets, ExponentialHistogramCounts, HistogramCounts};
+use opentelemetry::{InstrumentationScope, KeyValue};
+use std::{collections::HashMap, convert::TryFrom, sync::Mutex, time::SystemTime};
+
+/// Number of exports after which a series that was not exported anymore is forgotten.
+const MAX_STALE_EXPORTS: u64 = 10;
+
+/// Identifies a stream of cumulative data points: instrumentation scope (name, version, schema URL
+/// and attributes), metric and attributes.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub(crate) struct SeriesKey {
+    scope_name: String,
+    scope_version: Option<String>,
+    scope_schema_url: Option<String>,
+    scope_attrs: Vec<(String, String)>,
+    metric: String,
+    attrs: Vec<(String, String)>,
+}
+
+impl SeriesKey {
+    pub(crate) fn new<'a>(
+        scope: &InstrumentationScope,
+        metric: &str,
+        attrs: impl Iterator<Item = &'a KeyValue>,
+    ) -> Self {
+        Self {
+            scope_name: scope.name().into(),
+            scope_version: scope.version().map(Into::into),
+            scope_schema_url: scope.schema_url().map(Into::into),
+            scope_attrs: sorted(scope.attributes()),
+            metric: metric.into(),
+            attrs: sorted(attrs),
+        }
+    }
+}
+
+fn sorted<'a>(attrs: impl Iterator<Item = &'a KeyValue>) -> Vec<(String, String)> {
+    let mut attrs: Vec<_> = attrs
+        .map(|kv| (kv.key.to_string(), kv.value.to_string()))
+        .collect();
+    attrs.sort();
+    attrs
+}
+
+#[derive(Debug)]
+pub(crate) enum State {
+    Sum(f64),
+    Histogram(HistogramCounts),
+    ExponentialHistogram(ExponentialHistogramCounts),
+}
+
+/// Cumulative value, which can be converted to a delta.
+pub(crate) trait Cumulative: Clone + Sized {
+    /// Difference to the previous value, or `None` if the value was reset in between.
+    fn delta(&self, previous: &Self) -> Option<Self>;
+
+    fn to_state(&self) -> State;
+
+    fn from_state(state: &State) -> Option<&Self>;
+}
+
+#[derive(Debug)]
+struct Entry {
+    start_time: SystemTime,
+    export: u64,
+    state: State,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    export: u64,
+    series: HashMap<SeriesKey, Entry>,
+}
+
+/// Converts cumulative sums and histograms to deltas by remembering the last value of each series.
+#[derive(Debug, Default)]
+pub(crate) struct CumulativeToDelta {
+    inner: Mutex<Inner>,
+}
+
+/// Clones start without any series, because each clone of the exporter may be used by a different
+/// reader with its own cumulative values.
+impl Clone for CumulativeToDelta {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl CumulativeToDelta {
+    /// Delta since the last value of the series. The whole value is used for new series and after
+    /// resets, which are detected by a new start time or a lower value.
+    pub(crate) fn delta<T: Cumulative>(
+        &self,
+        key: SeriesKey,
+        start_time: SystemTime,
+        value: T,
+    ) -> T {
+        let mut inner = self.inner.lock().unwrap();
+        let export = inner.export;
+        let delta = inner
+            .series
+            .get(&key)
+            .filter(|entry| entry.start_time == start_time)
+            .and_then(|entry| T::from_state(&entry.state))
+            .and_then(|previous| value.delta(previous));
+        inner.series.insert(
+            key,
+            Entry {
+                start_time,
+                export,
+                state: value.to_state(),
+            },
+        );
+        delta.unwrap_or(value)
+    }
+
+    /// Finish an export and forget series, which haven't been exported for a while.
+    pub(crate) fn finish_export(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.export += 1;
+        let export = inner.export;
+        inner
+            .series
+            .retain(|_, entry| export - entry.export <= MAX_STALE_EXPORTS);
+    }
+}
+
+impl Cumulative for f64 {
+    fn delta(&self, previous: &Self) -> Option<Self> {
+        (self >= previous).then(|| self - previous)
+    }
+
+    fn to_state(&self) -> State {
+        State::Sum(*self)
+    }
+
+    fn from_state(state: &State) -> Option<&Self> {
+        match state {
+            State::Sum(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl Cumulative for HistogramCounts {
+    fn delta(&self, previous: &Self) -> Option<Self> {
+        if self.count < previous.count || self.bucket_counts.len() != previous.bucket_counts.len() {
+            return None;
+        }
+        let bucket_counts = self
+            .bucket_counts
+            .iter()
+            .zip(&previous.bucket_counts)
+            .map(|(count, previous)| count.checked_sub(*previous))
+            .collect::<Option<_>>()?;
+        Some(Self {
+            count: self.count - previous.count,
+            sum: self.sum - previous.sum,
+            // Min and max of the cumulative histogram are not the ones of the delta.
+            min: None,
+            max: None,
+            bucket_counts,
+        })
+    }
+
+    fn to_state(&self) -> State {
+        State::Histogram(self.clone())
+    }
+
+    fn from_state(state: &State) -> Option<&Self> {
+        match state {
+            State::Histogram(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl Cumulative for ExponentialHistogramCounts {
+    fn delta(&self, previous: &Self) -> Option<Self> {
+        // The scale of a cumulative histogram can only decrease.
+        let scale_change = u32::try_from(previous.scale - self.scale).ok()?;
+        Some(Self {
+            count: self.count.checked_sub(previous.count)?,
+            sum: self.sum - previous.sum,
+            min: None,
+            max: None,
+            scale: self.scale,
+            zero_count: self.zero_count.checked_sub(previous.zero_count)?,
+            positive: self
+                .positive
+                .delta(&previous.positive.downscale(scale_change))?,
+            negative: self
+                .negative
+                .delta(&previous.negative.downscale(scale_change))?,
+        })
+    }
+
+    fn to_state(&self) -> State {
+        State::ExponentialHistogram(self.clone())
+    }
+
+    fn from_state(state: &State) -> Option<&Self> {
+        match state {
+            State::ExponentialHistogram(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl ExponentialBuckets {
+    fn get(&self, index: i32) -> u64 {
+        usize::try_from(index - self.offset)
+            .ok()
+            .and_then(|i| self.counts.get(i))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Merge the buckets for a scale, which is lower by the given change.
+    fn downscale(&self, scale_change: u32) -> Self {
+        if scale_change == 0 || self.counts.is_empty() {
+            return self.clone();
+        }
+        let offset = self.offset >> scale_change;
+        let mut counts = Vec::new();
+        for (i, count) in self.counts.iter().enumerate() {
+            let index = ((self.offset + i as i32) >> scale_change) - offset;
+            let index = index as usize;
+            if counts.len() <= index {
+                counts.resize(index + 1, 0);
+            }
+            counts[index] += count;
+        }
+        Self { offset, counts }
+    }
+
+    fn delta(&self, previous: &Self) -> Option<Self> {
+        let counts = self
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| count.checked_sub(previous.get(self.offset + i as i32)))
+            .collect::<Option<_>>()?;
+        // Buckets of the previous value outside of the current range must be empty.
+        let previous_total: u64 = previous.counts.iter().sum();
+        let covered: u64 = (0..self.counts.len())
+            .map(|i| previous.get(self.offset + i as i32))
+            .sum();
+        (previous_total == covered).then_some(Self {
+            offset: self.offset,
+            counts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn key(attr: &'static str) -> SeriesKey {
+        SeriesKey::new(
+            &InstrumentationScope::builder("scope").build(),
+            "metric",
+            [KeyValue::new("a", attr)].iter(),
+        )
+    }
+
+    #[test]
+    fn sum_deltas_and_resets() {
+        let deltas = CumulativeToDelta::default();
+        let start = SystemTime::UNIX_EPOCH;
+        assert_eq!(deltas.delta(key("1"), start, 5.0), 5.0);
+        assert_eq!(deltas.delta(key("1"), start, 8.0), 3.0);
+        assert_eq!(deltas.delta(key("2"), start, 1.0), 1.0);
+        assert_eq!(deltas.delta(key("1"), start, 2.0), 2.0, "lower value");
+        let restart = start + Duration::from_secs(1);
+        assert_eq!(deltas.delta(key("1"), restart, 4.0), 4.0, "new start time");
+        assert_eq!(deltas.delta(key("1"), restart, 6.0), 2.0);
+    }
+
+    #[test]
+    fn series_of_different_scopes() {
+        let deltas = CumulativeToDelta::default();
+        let start = SystemTime::UNIX_EPOCH;
+        let key = |version: &'static str, attr: &'static str| {
+            let scope = InstrumentationScope::builder("scope")
+                .with_version(version)
+                .with_attributes([KeyValue::new("a", attr)])
+                .build();
+            SeriesKey::new(&scope, "metric", std::iter::empty())
+        };
+        assert_eq!(deltas.delta(key("1", "1"), start, 5.0), 5.0);
+        assert_eq!(deltas.delta(key("2", "1"), start, 3.0), 3.0);
+        assert_eq!(deltas.delta(key("1", "2"), start, 1.0), 1.0);
+        assert_eq!(deltas.delta(key("1", "1"), start, 8.0), 3.0);
+    }
+
+    #[test]
+    fn clones_start_without_series() {
+        let deltas = CumulativeToDelta::default();
+        let start = SystemTime::UNIX_EPOCH;
+        deltas.delta(key("1"), start, 5.0);
+        let clone = deltas.clone();
+        assert_eq!(clone.delta(key("1"), start, 8.0), 8.0);
+        assert_eq!(deltas.delta(key("1"), start, 8.0), 3.0);
+    }
+
+    #[test]
+    fn forgets_stale_series() {
+        let deltas = CumulativeToDelta::default();
+        let start = SystemTime::UNIX_EPOCH;
+        deltas.delta(key("1"), start, 5.0);
+        for _ in 0..=MAX_STALE_EXPORTS {
+            deltas.delta(key("2"), start, 1.0);
+            deltas.finish_export();
+        }
+        assert_eq!(deltas.delta(key("1"), start, 8.0), 8.0);
+        assert_eq!(deltas.delta(key("2"), start, 3.0), 2.0);
+    }
+
+    #[test]
+    fn histogram_deltas() {
+        let deltas = CumulativeToDelta::default();
+        let start = SystemTime::UNIX_EPOCH;
+        let histogram = |count, sum, bucket_counts| HistogramCounts {
+            count,
+            sum,
+            min: Some(1.0),
+            max: Some(9.0),
+            bucket_counts,
+        };
+        deltas.delta(key("1"), start, histogram(3, 10.0, vec![1, 2]));
+        assert_eq!(
+            deltas.delta(key("1"), start, histogram(5, 20.0, vec![2, 3])),
+            HistogramCounts {
+                count: 2,
+                sum: 10.0,
+                min: None,
+                max: None,
+                bucket_counts: vec![1, 1],
+            }
+        );
+    }
+
+    #[test]
+    fn exponential_histogram_deltas_with_downscale() {
+        let deltas = CumulativeToDelta::default();
+        let start = SystemTime::UNIX_EPOCH;
+        let histogram = |scale, positive: ExponentialBuckets| ExponentialHistogramCounts {
+            count: positive.counts.iter().sum(),
+            sum: 0.0,
+            min: None,
+            max: None,
+            scale,
+            zero_count: 0,
+            positive,
+            negative: ExponentialBuckets::default(),
+        };
+        deltas.delta(
+            key("1"),
+            start,
+            histogram(
+                1,
+                ExponentialBuckets {
+                    offset: 1,
+                    counts: vec![1, 2, 3],
+                },
+            ),
+        );
+        let delta = deltas.delta(
+            key("1"),
+            start,
+            histogram(
+                0,
+                ExponentialBuckets {
+                    offset: 0,
+                    counts: vec![2, 6],
+                },
+            ),
+        );
+        assert_eq!(
+            delta.positive,
+            ExponentialBuckets {
+                offset: 0,
+                counts: vec![1, 1],
+            }
+        );
+        assert_eq!(delta.count, 2);
+    }
+}
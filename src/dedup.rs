@@ -0,0 +1,368 @@
+use crate::{
+    convert::time_to_string,
+    models::{context_tag_keys as tags, Data, Envelope, Properties, SeverityLevel},
+    Exporter, HttpClient,
+};
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Maximum number of distinct messages and exceptions tracked at the same time. Further ones are
+/// sent without deduplication.
+const MAX_ENTRIES: usize = 10_000;
+
+/// Property of the summary envelope with the number of occurrences in the window.
+const PROPERTY_OCCURRENCES: &str = "occurrences";
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct Key {
+    name: &'static str,
+    /// Message of a message or type name of an exception.
+    text: String,
+    severity_level: Option<SeverityLevel>,
+    operation_name: Option<String>,
+}
+
+impl Key {
+    fn new(envelope: &Envelope) -> Option<Self> {
+        let (text, severity_level) = match envelope.data.as_ref()? {
+            Data::Message(data) => (data.message.as_ref(), data.severity_level),
+            Data::Exception(data) => (
+                data.exceptions.first()?.type_name.as_ref(),
+                data.severity_level,
+            ),
+            _ => return None,
+        };
+        Some(Self {
+            name: envelope.name,
+            text: text.to_string(),
+            severity_level,
+            operation_name: envelope
+                .tags
+                .as_ref()
+                .and_then(|tags| tags.get(&tags::OPERATION_NAME))
+                .cloned(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    window_start: Instant,
+    occurrences: usize,
+    first: Envelope,
+}
+
+/// Deduplicates messages and exceptions with the same message or type, severity level and
+/// operation name within a time window.
+///
+/// The first occurrence is sent right away. The following ones are counted, and once the window
+/// is over a copy of the first occurrence is sent with the number of occurrences in the window.
+#[derive(Debug)]
+pub(crate) struct Deduplicator {
+    window: Duration,
+    entries: Mutex<HashMap<Key, Entry>>,
+}
+
+impl Deduplicator {
+    pub(crate) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Envelopes to send: new occurrences, other envelopes, and the summaries of the windows which
+    /// are over.
+    pub(crate) fn process(&self, envelopes: Vec<Envelope>, now: Instant) -> Vec<Envelope> {
+        let mut entries = self.entries.lock().unwrap();
+        let mut result = Vec::with_capacity(envelopes.len());
+        for envelope in envelopes {
+            let key = match Key::new(&envelope) {
+                Some(key) => key,
+                None => {
+                    result.push(envelope);
+                    continue;
+                }
+            };
+            if let Some(entry) = entries.get_mut(&key) {
+                if now.duration_since(entry.window_start) < self.window {
+                    entry.occurrences += 1;
+                    continue;
+                }
+                if let Some(entry) = entries.remove(&key) {
+                    result.extend(summary(entry));
+                }
+            }
+            if entries.len() < MAX_ENTRIES {
+                if let Some(first) = copy(&envelope) {
+                    entries.insert(
+                        key,
+                        Entry {
+                            window_start: now,
+                            occurrences: 1,
+                            first,
+                        },
+                    );
+                }
+            }
+            result.push(envelope);
+        }
+
+        let (expired, active): (HashMap<_, _>, _) = std::mem::take(&mut *entries)
+            .into_iter()
+            .partition(|(_, entry)| now.duration_since(entry.window_start) >= self.window);
+        *entries = active;
+        result.extend(expired.into_values().filter_map(summary));
+        result
+    }
+
+    /// Summaries of all windows, including the ones which are not over yet. Following occurrences
+    /// start new windows.
+    pub(crate) fn flush(&self) -> Vec<Envelope> {
+        std::mem::take(&mut *self.entries.lock().unwrap())
+            .into_values()
+            .filter_map(summary)
+            .collect()
+    }
+}
+
+impl<C> Exporter<C> {
+    pub(crate) fn deduplicate(&self, envelopes: Vec<Envelope>) -> Vec<Envelope> {
+        match &self.deduplicator {
+            Some(deduplicator) => deduplicator.process(envelopes, Instant::now()),
+            None => envelopes,
+        }
+    }
+}
+
+impl<C: HttpClient + 'static> Exporter<C> {
+    /// Upload the summaries of all deduplication windows. Summaries are otherwise only sent with
+    /// the next export, which may never come after the last occurrences before a shutdown.
+    ///
+    /// The upload runs on the current thread and is given up after the timeout, e.g. when the
+    /// HTTP client depends on an async runtime which is blocked by this call.
+    pub(crate) fn flush_deduplication(&self, timeout: Duration) -> OTelSdkResult {
+        let envelopes = match &self.deduplicator {
+            Some(deduplicator) => deduplicator.flush(),
+            None => return Ok(()),
+        };
+        if envelopes.is_empty() {
+            return Ok(());
+        }
+        let upload = crate::uploader::send(
+            self.client.as_ref(),
+            self.track_endpoint.as_ref(),
+            envelopes,
+            self.retry_notify.clone(),
+        );
+        match block_on_with_timeout(upload, timeout) {
+            Some(result) => result.map_err(Into::into),
+            None => Err(OTelSdkError::Timeout(timeout)),
+        }
+    }
+}
+
+/// Wakes the thread which polls a future in [`block_on_with_timeout`].
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Poll the future on the current thread until it completes or the timeout is over.
+fn block_on_with_timeout<F: Future>(future: F, timeout: Duration) -> Option<F::Output> {
+    let deadline = Instant::now() + timeout;
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return Some(output);
+        }
+        let remaining = deadline.checked_duration_since(Instant::now())?;
+        thread::park_timeout(remaining);
+    }
+}
+
+/// Copy of a message or exception envelope. Other telemetry is not deduplicated.
+fn copy(envelope: &Envelope) -> Option<Envelope> {
+    let data = match envelope.data.as_ref()? {
+        Data::Message(data) => Data::Message(data.clone()),
+        Data::Exception(data) => Data::Exception(data.clone()),
+        _ => return None,
+    };
+    Some(Envelope {
+        name: envelope.name,
+        time: envelope.time.clone(),
+        sample_rate: envelope.sample_rate,
+        i_key: envelope.i_key.clone(),
+        tags: envelope.tags.clone(),
+        data: Some(data),
+    })
+}
+
+/// Summary envelope of a window, if there was more than one occurrence.
+fn summary(entry: Entry) -> Option<Envelope> {
+    if entry.occurrences <= 1 {
+        return None;
+    }
+    let mut envelope = entry.first;
+    envelope.time = time_to_string(SystemTime::now()).into();
+    let properties = match envelope.data.as_mut()? {
+        Data::Message(data) => &mut data.properties,
+        Data::Exception(data) => &mut data.properties,
+        _ => return None,
+    };
+    properties.get_or_insert_with(Properties::new).insert(
+        PROPERTY_OCCURRENCES.into(),
+        entry.occurrences.to_string().into(),
+    );
+    Some(envelope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{context_tag_keys::Tags, MessageData};
+
+    fn message(message: &str, operation_id: &str) -> Envelope {
+        let mut tags = Tags::new();
+        tags.insert(tags::OPERATION_ID, operation_id.into());
+        tags.insert(tags::OPERATION_NAME, "GET /".into());
+        Envelope {
+            name: "Microsoft.ApplicationInsights.Message",
+            time: time_to_string(SystemTime::UNIX_EPOCH).into(),
+            sample_rate: None,
+            i_key: None,
+            tags: Some(tags),
+            data: Some(Data::Message(MessageData {
+                ver: 2,
+                message: message.into(),
+                severity_level: Some(SeverityLevel::Error),
+                properties: None,
+            })),
+        }
+    }
+
+    fn describe(envelopes: &[Envelope]) -> Vec<(String, Option<String>)> {
+        envelopes
+            .iter()
+            .map(|envelope| {
+                let operation_id = envelope
+                    .tags
+                    .as_ref()
+                    .and_then(|tags| tags.get(&tags::OPERATION_ID))
+                    .cloned()
+                    .unwrap_or_default();
+                let occurrences = match &envelope.data {
+                    Some(Data::Message(data)) => data
+                        .properties
+                        .as_ref()
+                        .and_then(|x| x.get(&PROPERTY_OCCURRENCES.into()))
+                        .map(|x| x.as_ref().to_string()),
+                    _ => None,
+                };
+                (operation_id, occurrences)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn forwards_first_and_summarizes_window() {
+        let dedup = Deduplicator::new(Duration::from_secs(60));
+        let start = Instant::now();
+
+        let sent = dedup.process(
+            vec![
+                message("failed", "1"),
+                message("failed", "2"),
+                message("other", "3"),
+            ],
+            start,
+        );
+        assert_eq!(
+            describe(&sent),
+            vec![("1".into(), None), ("3".into(), None)]
+        );
+
+        let sent = dedup.process(
+            vec![message("failed", "4")],
+            start + Duration::from_secs(30),
+        );
+        assert!(sent.is_empty());
+
+        let sent = dedup.process(vec![], start + Duration::from_secs(60));
+        assert_eq!(describe(&sent), vec![("1".into(), Some("3".into()))]);
+
+        let sent = dedup.process(
+            vec![message("failed", "5")],
+            start + Duration::from_secs(61),
+        );
+        assert_eq!(describe(&sent), vec![("5".into(), None)]);
+    }
+
+    #[test]
+    fn flush_pending_windows() {
+        let dedup = Deduplicator::new(Duration::from_secs(60));
+        let start = Instant::now();
+
+        dedup.process(
+            vec![
+                message("failed", "1"),
+                message("failed", "2"),
+                message("other", "3"),
+            ],
+            start,
+        );
+        assert_eq!(
+            describe(&dedup.flush()),
+            vec![("1".into(), Some("2".into()))]
+        );
+        assert!(dedup.flush().is_empty());
+
+        let sent = dedup.process(vec![message("failed", "4")], start + Duration::from_secs(1));
+        assert_eq!(describe(&sent), vec![("4".into(), None)]);
+    }
+
+    #[test]
+    fn new_window_after_expiry() {
+        let dedup = Deduplicator::new(Duration::from_secs(60));
+        let start = Instant::now();
+
+        dedup.process(vec![message("failed", "1"), message("failed", "2")], start);
+        let sent = dedup.process(
+            vec![message("failed", "3")],
+            start + Duration::from_secs(90),
+        );
+        assert_eq!(
+            describe(&sent),
+            vec![("1".into(), Some("2".into())), ("3".into(), None)]
+        );
+    }
+
+    #[test]
+    fn block_on_with_timeout_gives_up() {
+        assert_eq!(
+            block_on_with_timeout(std::future::ready(1), Duration::from_millis(10)),
+            Some(1)
+        );
+        assert_eq!(
+            block_on_with_timeout(std::future::pending::<()>(), Duration::from_millis(10)),
+            None
+        );
+    }
+}
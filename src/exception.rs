@@ -0,0 +1,247 @@
+#[cfg(feature = "trace")]
+use crate::trace::EVENT_NAME_EXCEPTION;
+use crate::{
+    convert::AttrValue,
+    models::{context_tag_keys::attrs::EXCEPTION_SOURCE_MESSAGES, ExceptionDetails, SeverityLevel},
+    stack_trace::parse_stack_trace,
+};
+#[cfg(feature = "trace")]
+use opentelemetry::{
+    trace::{Span as _, SpanRef},
+    Array, KeyValue, StringValue, Value,
+};
+use opentelemetry_semantic_conventions as semcov;
+use std::collections::HashMap;
+#[cfg(feature = "trace")]
+use std::{
+    backtrace::{Backtrace, BacktraceStatus},
+    error::Error,
+};
+
+/// Extension trait for spans to record errors in more detail.
+#[cfg(feature = "trace")]
+#[cfg_attr(docsrs, doc(cfg(feature = "trace")))]
+pub trait SpanExt {
+    /// Record an error including the chain of its sources (see [`Error::source`]) as an exception
+    /// event.
+    ///
+    /// The error and each of its sources are sent as separate, nested exceptions. The type name of
+    /// the error is sent as the exception type (the types of sources are not known), unless the
+    /// error is a trait object like `dyn Error`. If backtraces are enabled (see
+    /// [`Backtrace::capture`]), a backtrace is captured and sent as the exception's call stack.
+    /// Note that it shows where this method is called, not where the error was created.
+    ///
+    /// ```
+    /// use opentelemetry::trace::{Tracer, TracerProvider as _};
+    /// use opentelemetry_application_insights::SpanExt as _;
+    ///
+    /// # let tracer = opentelemetry_sdk::trace::SdkTracerProvider::builder().build().tracer("test");
+    /// let mut span = tracer.start("read config");
+    /// if let Err(error) = std::fs::read_to_string("config.toml") {
+    ///     span.record_error_chain(&error);
+    /// }
+    /// ```
+    fn record_error_chain<E>(&mut self, error: &E)
+    where
+        E: Error + ?Sized + 'static;
+}
+
+#[cfg(feature = "trace")]
+macro_rules! impl_span_ext {
+    ($($ty:ty),*) => {
+        $(impl SpanExt for $ty {
+            fn record_error_chain<E>(&mut self, error: &E)
+            where
+                E: Error + ?Sized + 'static,
+            {
+                self.add_event(EVENT_NAME_EXCEPTION, error_chain_attributes(error));
+            }
+        })*
+    };
+}
+
+#[cfg(feature = "trace")]
+impl_span_ext!(
+    opentelemetry_sdk::trace::Span,
+    opentelemetry::global::BoxedSpan,
+    SpanRef<'_>
+);
+
+#[cfg(feature = "trace")]
+fn error_chain_attributes<E>(error: &E) -> Vec<KeyValue>
+where
+    E: Error + ?Sized + 'static,
+{
+    let mut attributes = vec![KeyValue::new(
+        semcov::trace::EXCEPTION_MESSAGE,
+        error.to_string(),
+    )];
+    let type_name = std::any::type_name::<E>();
+    // Trait objects only tell `dyn core::error::Error`, not the actual type.
+    if !type_name.starts_with("dyn ") {
+        attributes.push(KeyValue::new(semcov::trace::EXCEPTION_TYPE, type_name));
+    }
+    let sources: Vec<StringValue> = std::iter::successors(error.source(), |&x| x.source())
+        .map(|x| x.to_string().into())
+        .collect();
+    if !sources.is_empty() {
+        attributes.push(KeyValue::new(
+            EXCEPTION_SOURCE_MESSAGES,
+            Value::Array(Array::String(sources)),
+        ));
+    }
+    let backtrace = Backtrace::capture();
+    if backtrace.status() == BacktraceStatus::Captured {
+        attributes.push(KeyValue::new(
+            semcov::trace::EXCEPTION_STACKTRACE,
+            backtrace.to_string(),
+        ));
+    }
+    attributes
+}
+
+/// Convert the exception attributes to exception details, removing them from the map.
+///
+/// The exception sources become nested exceptions after the outermost exception.
+pub(crate) fn attrs_to_exception_details(
+    attrs: &mut HashMap<&str, &dyn AttrValue>,
+    no_type: &str,
+    no_message: &str,
+) -> Vec<ExceptionDetails> {
+    let stack = attrs.remove(semcov::trace::EXCEPTION_STACKTRACE);
+    let parsed_stack = stack.and_then(|stack| parse_stack_trace(&stack.as_str()));
+    let mut exception = ExceptionDetails {
+        id: None,
+        outer_id: None,
+        type_name: attrs
+            .remove(semcov::trace::EXCEPTION_TYPE)
+            .map(Into::into)
+            .unwrap_or_else(|| no_type.into()),
+        message: attrs
+            .remove(semcov::trace::EXCEPTION_MESSAGE)
+            .map(Into::into)
+            .unwrap_or_else(|| no_message.into()),
+        has_full_stack: parsed_stack.as_ref().map(|x| x.has_full_stack),
+        stack: stack.map(Into::into),
+        parsed_stack: parsed_stack.map(|x| x.frames),
+    };
+
+    let source_messages = attrs
+        .remove(EXCEPTION_SOURCE_MESSAGES)
+        .and_then(|x| x.as_str_list())
+        .unwrap_or_default();
+    if source_messages.is_empty() {
+        return vec![exception];
+    }
+
+    exception.id = Some(0);
+    let mut exceptions = Vec::with_capacity(1 + source_messages.len());
+    exceptions.push(exception);
+    for (i, message) in source_messages.into_iter().enumerate() {
+        let id = i as i32 + 1;
+        exceptions.push(ExceptionDetails {
+            id: Some(id),
+            outer_id: Some(id - 1),
+            type_name: no_type.into(),
+            message: message.into(),
+            has_full_stack: None,
+            stack: None,
+            parsed_stack: None,
+        });
+    }
+    exceptions
+}
+
+/// Severity level based on whether the exception escaped the scope of a span, removing the
+/// attribute from the map.
+pub(crate) fn attrs_to_escaped_severity_level(
+    attrs: &mut HashMap<&str, &dyn AttrValue>,
+) -> Option<SeverityLevel> {
+    #[allow(deprecated)]
+    let escaped = attrs.remove(semcov::trace::EXCEPTION_ESCAPED)?;
+    Some(if escaped.as_str() == "true" {
+        SeverityLevel::Critical
+    } else {
+        SeverityLevel::Error
+    })
+}
+
+#[cfg(all(test, feature = "trace"))]
+mod tests {
+    use super::*;
+    use crate::convert::attrs_to_map;
+
+    #[test]
+    fn nested_exceptions() {
+        let attributes = [
+            KeyValue::new(semcov::trace::EXCEPTION_MESSAGE, "outer"),
+            KeyValue::new(
+                EXCEPTION_SOURCE_MESSAGES,
+                Value::Array(Array::String(vec!["middle".into(), "inner".into()])),
+            ),
+        ];
+        let mut attrs = attrs_to_map(attributes.iter());
+        let exceptions = attrs_to_exception_details(&mut attrs, "<none>", "<none>");
+        assert!(attrs.is_empty());
+        let simplified: Vec<_> = exceptions
+            .iter()
+            .map(|x| (x.id, x.outer_id, x.type_name.as_ref(), x.message.as_ref()))
+            .collect();
+        assert_eq!(
+            simplified,
+            vec![
+                (Some(0), None, "<none>", "outer"),
+                (Some(1), Some(0), "<none>", "middle"),
+                (Some(2), Some(1), "<none>", "inner"),
+            ]
+        );
+    }
+
+    #[test]
+    fn error_chain() {
+        #[derive(Debug)]
+        struct Outer(std::io::Error);
+
+        impl std::fmt::Display for Outer {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("outer")
+            }
+        }
+
+        impl Error for Outer {
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        let attributes = error_chain_attributes(&Outer(std::io::Error::other("inner")));
+        let mut attrs = attrs_to_map(attributes.iter());
+        attrs.remove(semcov::trace::EXCEPTION_STACKTRACE);
+        let exceptions = attrs_to_exception_details(&mut attrs, "", "");
+        let simplified: Vec<_> = exceptions
+            .iter()
+            .map(|x| (x.type_name.as_ref(), x.message.as_ref()))
+            .collect();
+        assert_eq!(
+            simplified,
+            vec![
+                (
+                    "opentelemetry_application_insights::exception::tests::error_chain::Outer",
+                    "outer"
+                ),
+                ("", "inner"),
+            ]
+        );
+
+        let error: Box<dyn Error> = Box::new(Outer(std::io::Error::other("inner")));
+        let attributes = error_chain_attributes(error.as_ref());
+        let mut attrs = attrs_to_map(attributes.iter());
+        attrs.remove(semcov::trace::EXCEPTION_STACKTRACE);
+        let exceptions = attrs_to_exception_details(&mut attrs, "<no type>", "");
+        assert_eq!(
+            exceptions[0].type_name.as_ref(),
+            "<no type>",
+            "trait object"
+        );
+    }
+}
@@ -164,10 +164,10 @@ async fn main() {
 //! Map async/sync clients with the appropriate builder methods:
 //!
 //! - Sync clients with `{SdkTracerProvider,SdkLoggerProvider}.with_batch_exporter`/`PeriodicReader::builder`. If you're already in an
-//! async context when creating the client, you might need to create it using
-//! `std::thread::spawn(reqwest::blocking::Client::new).join().unwrap()`.
+//!   async context when creating the client, you might need to create it using
+//!   `std::thread::spawn(reqwest::blocking::Client::new).join().unwrap()`.
 //! - Async clients with the corresponding experimental async APIs. _Or_ with the pipeline API and
-//! `build_batch`/`install_batch`.
+//!   `build_batch`/`install_batch`.
 //!
 //! # Attribute mapping
 //!
@@ -193,16 +193,36 @@ async fn main() {
 //! | `k8s.job.name`                                 | Context: Cloud role (`ai.cloud.role`)                    |
 //! | `k8s.cronjob.name`                             | Context: Cloud role (`ai.cloud.role`)                    |
 //! | `k8s.daemonset.name`                           | Context: Cloud role (`ai.cloud.role`)                    |
+//! | `faas.name`                                    | Context: Cloud role (`ai.cloud.role`)                    |
+//! | `azure.vm.scaleset.name`                       | Context: Cloud role (`ai.cloud.role`)                    |
+//! | `cloud.resource_id` (last segment)             | Context: Cloud role (`ai.cloud.role`)                    |
 //! | `k8s.pod.name`                                 | Context: Cloud role instance (`ai.cloud.roleInstance`)   |
 //! | `service.instance.id`                          | Context: Cloud role instance (`ai.cloud.roleInstance`)   |
+//! | `faas.instance`                                | Context: Cloud role instance (`ai.cloud.roleInstance`)   |
+//! | `host.name`                                    | Context: Cloud role instance (`ai.cloud.roleInstance`)   |
 //! | `device.id`                                    | Context: Device id (`ai.device.id`)                      |
+//! | `host.id`                                      | Context: Device id (`ai.device.id`)                      |
 //! | `device.model.name`                            | Context: Device model (`ai.device.model`)                |
+//! | `host.type`                                    | Context: Device model (`ai.device.model`)                |
+//! | `host.arch`                                    | Context: Device model (`ai.device.model`)                |
+//! | `os.description`                               | Context: Device OS version (`ai.device.osVersion`)       |
+//! | `os.name` or `os.type` + `os.version`          | Context: Device OS version (`ai.device.osVersion`)       |
+//! | `host.*`, `os.*` or `process.runtime.*`        | Context: Device type (`ai.device.type`) `"PC"`           |
 //! | `service.version`                              | Context: Application version (`ai.application.ver`)      |
 //! | `telemetry.sdk.name` + `telemetry.sdk.version` | Context: Internal SDK version (`ai.internal.sdkVersion`) |
 //! | `ai.*`                                         | Context: AppInsights Tag (`ai.*`)                        |
 //!
 //! If `service.name` is the default (i.e. starts with "unknown_service:"), the Kubernetes based
-//! values take precedence.
+//! values take precedence, followed by `faas.name`, `azure.vm.scaleset.name` and the resource name
+//! in `cloud.resource_id`.
+//!
+//! For fields with multiple attributes, the first attribute in the table that exists wins. E.g.
+//! the cloud role instance is `k8s.pod.name`, `service.instance.id`, `faas.instance` or, as in
+//! live metrics, `host.name`.
+//!
+//! Use the [`AzureResourceDetector`] to detect these attributes on Azure App Service, Azure
+//! Functions, Azure Container Apps and AKS, and the [`AzureVmResourceDetector`] on Azure virtual
+//! machines and scale sets.
 //!
 //! ## Spans
 //!
@@ -214,20 +234,33 @@ async fn main() {
 //! | `SERVER`, `CONSUMER`             | [Request]                           |
 //!
 //! The Span's status determines the Success field of a Dependency or Request. Success is `false` if
-//! the status `Error`; otherwise `true`.
+//! the status `Error`; otherwise `true`. If the status is `Unset`, the RPC status attributes
+//! `rpc.grpc.status_code`, `rpc.connect_rpc.error_code` and `rpc.jsonrpc.error_code` determine
+//! success the way the semantic conventions for [RPC] define it. Use
+//! [`Exporter::with_success_policy`] to override this, e.g. based on HTTP status codes.
 //!
 //! The following of the Span's attributes map to special fields in Application Insights (the
 //! mapping tries to follow the OpenTelemetry semantic conventions for [trace]).
 //!
 //! Note: for `INTERNAL` Spans the Dependency Type is always `"InProc"`.
 //!
+//! Spans with the attribute [`attrs::PAGE_VIEW`] set to `true` are converted into [PageView]
+//! telemetry instead, regardless of their kind. The span name becomes the page name, `url.full`
+//! the URL and `http.request.header.referer` the referrer. Set [`attrs::SESSION_ID`] and
+//! [`attrs::USER_ID`] to make the usage features of Application Insights work.
+//!
 //! [trace]: https://github.com/open-telemetry/opentelemetry-specification/tree/master/specification/trace/semantic_conventions
+//! [RPC]: https://opentelemetry.io/docs/specs/semconv/rpc/rpc-spans/
 //! [Dependency]: https://learn.microsoft.com/en-us/azure/azure-monitor/app/data-model-dependency-telemetry
 //! [Request]: https://learn.microsoft.com/en-us/azure/azure-monitor/app/data-model-request-telemetry
+//! [PageView]: https://learn.microsoft.com/en-us/azure/azure-monitor/app/data-model-complete#pageview
 //!
 //! | OpenTelemetry attribute key                                                | Application Insights field                               |
 //! | -------------------------------------------------------------------------- | -------------------------------------------------------- |
 //! | `user.id`                                                                  | Context: Authenticated user id (`ai.user.authUserId`)    |
+//! | `enduser.pseudo.id`                                                        | Context: Anonymous user id (`ai.user.id`)                |
+//! | `session.id`                                                               | Context: Session id (`ai.session.id`)                    |
+//! | `client.address` (for server spans)                                        | Context: Client IP (`ai.location.ip`)                    |
 //! | `SpanKind::Server` + `http.request.method` + `http.route`                  | Context: Operation Name (`ai.operation.name`)            |
 //! | `ai.*`                                                                     | Context: AppInsights Tag (`ai.*`)                        |
 //! | `url.full`                                                                 | Dependency Data                                          |
@@ -236,22 +269,57 @@ async fn main() {
 //! | `server.address` + `server.port`                                           | Dependency Target                                        |
 //! | `network.peer.address` + `network.peer.port`                               | Dependency Target                                        |
 //! | `db.namespace`                                                             | Dependency Target                                        |
+//! | `server.address` + `messaging.destination.name`                            | Dependency Target                                        |
+//! | `rpc.service`                                                              | Dependency Target                                        |
+//! | `server.address` + `messaging.destination.name` (for `az.namespace` spans) | Dependency Target                                        |
+//! | `messaging.operation.name` + `messaging.destination.name`                  | Dependency Name                                          |
+//! | `rpc.service` + `rpc.method`                                               | Dependency Name                                          |
+//! | `gen_ai.operation.name` + `gen_ai.request.model`                           | Dependency Name                                          |
 //! | `http.response.status_code`                                                | Dependency Result code                                   |
+//! | `rpc.grpc.status_code`                                                     | Dependency Result code                                   |
+//! | `rpc.connect_rpc.error_code` or `rpc.jsonrpc.error_code`                   | Dependency Result code                                   |
+//! | `gen_ai.provider.name`                                                     | Dependency Type                                          |
 //! | `db.system.name`                                                           | Dependency Type                                          |
-//! | `messaging.system`                                                         | Dependency Type                                          |
-//! | `rpc.system`                                                               | Dependency Type                                          |
+//! | `"Queue Message \| "` + `messaging.system`                                 | Dependency Type                                          |
+//! | `rpc.system` (`"GRPC"` for `grpc`)                                         | Dependency Type                                          |
 //! | `"HTTP"` if any `http.` attribute exists                                   | Dependency Type                                          |
 //! | `"DB"` if any `db.` attribute exists                                       | Dependency Type                                          |
+//! | `az.namespace`                                                             | Dependency Type (see below)                              |
+//! | `gen_ai.usage.*` (numeric values)                                          | Dependency Measurements                                  |
 //! | `url.full`                                                                 | Request Url                                              |
 //! | `url.scheme` + `http.request.header.host` + `url.path` + `url.query`       | Request Url                                              |
 //! | `url.scheme` + `server.address` + `server.port` + `url.path` + `url.query` | Request Url                                              |
 //! | `client.address`                                                           | Request Source                                           |
 //! | `network.peer.address`                                                     | Request Source                                           |
+//! | `server.address` + `messaging.destination.name` (for `CONSUMER` spans)     | Request Source                                           |
+//! | `messaging.message.enqueued_time` or links with `enqueuedTime`             | Request Measurement `timeSinceEnqueued`                  |
 //! | `http.response.status_code`                                                | Request Response code                                    |
+//! | `rpc.grpc.status_code`                                                     | Request Response code                                    |
+//! | `rpc.connect_rpc.error_code` or `rpc.jsonrpc.error_code`                   | Request Response code                                    |
 //!
-//! All other attributes are directly converted to custom properties.
+//! All other attributes are directly converted to custom properties. This includes
+//! `user_agent.original`, as there is no context tag for the user agent. Use
+//! [`Exporter::with_measurements_policy`] to send numeric attributes as custom measurements
+//! instead.
 //!
-//! For Requests the attributes `http.request.method` and `http.route` override the Name.
+//! For Requests the attributes `http.request.method` and `http.route` override the Name. For RPC
+//! Requests and Dependencies the attributes `rpc.service` and `rpc.method` override the Name.
+//!
+//! Spans created by Azure SDKs carry an `az.namespace` attribute, which determines the Dependency
+//! Type the same way the official Azure Monitor exporters do, e.g. `"Azure Service Bus"` for
+//! `Microsoft.ServiceBus` or `"Azure blob"` for `Microsoft.Storage` calls to blob storage.
+//! `PRODUCER` spans use `"Queue Message | <type>"` and `INTERNAL` spans `"InProc | <az.namespace>"`.
+//! The Dependency Target and, for `CONSUMER` spans, the Request Source are `<host>/<entity>`. The
+//! attributes `peer.address` and `message_bus.destination` used by older Azure SDKs also work.
+//!
+//! Only Requests get an Operation Name from their own attributes. Add the
+//! [`OperationContextSpanProcessor`] to the tracer provider to set the Operation Name of the root
+//! Request on all Dependencies, Events and Logs of the same trace.
+//!
+//! Requests from availability tests, health checks and bots can be marked as synthetic traffic
+//! (`ai.operation.syntheticSource`) with [`Exporter::with_synthetic_source_detector`], see
+//! [`SyntheticSourceDetector`]. With the [`OperationContextSpanProcessor`] the synthetic source is
+//! set on all telemetry of the same trace as well.
 //!
 //! ### Deprecated attributes
 //!
@@ -263,6 +331,8 @@ async fn main() {
 //! | `db.namespace`              | `db.name`                                  |
 //! | `db.query.text`             | `db.statement`                             |
 //! | `db.system.name`            | `db.system`                                |
+//! | `messaging.operation.name`  | `messaging.operation`                      |
+//! | `gen_ai.provider.name`      | `gen_ai.system`                            |
 //! | `http.request.method`       | `http.method`                              |
 //! | `http.request.header.host`  | `http.host`                                |
 //! | `http.response.status_code` | `http.status_code`                         |
@@ -294,6 +364,15 @@ async fn main() {
 //! | `exception.message`         | Exception message          |
 //! | `exception.stacktrace`      | Exception call stack       |
 //!
+//! The call stack is also parsed into structured stack frames. This works for the output of Rust's
+//! `std::backtrace::Backtrace` and the `backtrace` crate, as well as common .NET, Java, Python and
+//! JavaScript formats. Frames from the middle of very long stacks are dropped.
+//!
+//! The attribute `exception.escaped` determines the severity level: `Critical` if `true`,
+//! otherwise `Error`. The messages of the sources of an error can be recorded in the attribute
+//! [`attrs::EXCEPTION_SOURCE_MESSAGES`], which are sent as nested exceptions.
+//! [`SpanExt::record_error_chain`] does this for a [`std::error::Error`].
+//!
 //! Events are converted into [Event] telemetry if the event name equals `"ai.custom"` with the
 //! following mapping:
 //!
@@ -309,6 +388,10 @@ async fn main() {
 //!
 //! All other attributes are directly converted to custom properties.
 //!
+//! Events inherit the context tags of their span, e.g. the user id, the operation name and any
+//! [`attrs`] set on the span. Span attributes are not copied into event properties by default,
+//! but selected ones can be with [`Exporter::with_span_attributes_in_events`].
+//!
 //! [exceptions]: https://github.com/open-telemetry/opentelemetry-specification/blob/master/specification/trace/exceptions.md
 //! [Exception]: https://learn.microsoft.com/en-us/azure/azure-monitor/app/data-model-exception-telemetry
 //! [Event]: https://learn.microsoft.com/en-us/azure/azure-monitor/app/data-model-event-telemetry
@@ -321,19 +404,56 @@ async fn main() {
 //!
 //! - If they contain an `exception.type` or `exception.message` attribute, they're converted to
 //!   [Exception] telemetry with the same attribute mapping as events.
+//! - If they contain a `microsoft.custom_event.name` attribute or have an event name, they're
+//!   converted to [Event] telemetry with that name. Numeric attributes become custom measurements
+//!   and all other attributes custom properties. The names `tracing` generates for unnamed events
+//!   (`event <file>:<line>`) are ignored.
 //! - Otherwise they're converted to [Trace] telemetry.
 //!
+//! Structured values, i.e. lists and maps in attributes and log bodies, are sent as JSON, so they
+//! can be parsed with `parse_json` in queries. Use [`Exporter::with_log_body_flattening`] to send
+//! the entries of map bodies as individual custom properties instead.
+//!
+//! Use [`Exporter::with_log_filter`] to upload only some of the logs, configured with
+//! `RUST_LOG`-style directives like `info,hyper=warn` (see [`LogFilter`]).
+//! [`Exporter::with_deduplication_window`] uploads repeated logs and exceptions only once per
+//! time window, followed by a summary with the number of occurrences.
+//!
+//! The severity level is taken from the severity number or, if that is missing, from common
+//! severity texts like `warn` or `critical`. The name and version of the instrumentation scope are
+//! sent as the custom properties `CategoryName` and `CategoryVersion`, and the attributes
+//! `code.file.path` and `code.line.number` are combined into the custom property `SourceLocation`
+//! (`<file>:<line>`).
+//!
 //! ## Metrics
 //!
 //! Metrics get reported to Application Insights as Metric Data. The [`Aggregation`] determines how
 //! the data is represented.
 //!
-//! | Aggregator           | Data representation                                                  |
-//! | -------------------- | -------------------------------------------------------------------- |
-//! | Histogram            | aggregation with sum, count, min, and max (buckets are not exported) |
-//! | ExponentialHistogram | aggregation with sum, count, min, and max (buckets are not exported) |
-//! | Gauge                | one measurement                                                      |
-//! | Sum                  | aggregation with only a value                                        |
+//! | Aggregator           | Data representation                                                   |
+//! | -------------------- | --------------------------------------------------------------------- |
+//! | Histogram            | aggregation with sum, count, min, max, and estimated std. deviation   |
+//! | ExponentialHistogram | aggregation with sum, count, min, max, and estimated std. deviation   |
+//! | Gauge                | one measurement                                                       |
+//! | Sum                  | aggregation with only a value                                         |
+//!
+//! The standard deviation of histograms is estimated from the bucket counts. Use
+//! [`Exporter::with_histogram_percentiles`] to also send estimated percentiles like p95 and p99 as
+//! additional metrics.
+//!
+//! Metrics are sent to the default namespace with their name unchanged. Use
+//! [`Exporter::with_metric_naming_policy`] to use the meter name or a fixed value as namespace,
+//! append units to names, and sanitize names (see [`MetricNamingPolicy`]).
+//!
+//! The exporter asks for delta temporality by default. Use [`Exporter::with_temporality`] to ask
+//! for cumulative temporality instead, e.g. when sharing views with pull-based readers. The
+//! exporter converts cumulative sums and histograms to deltas. Only the presets of the SDK's
+//! [`Temporality`] are supported, not a temporality per instrument kind.
+//!
+//! Application Insights computes its standard metrics (like server response time and failed
+//! requests) from the request and dependency telemetry, which is inaccurate with sampling. Use the
+//! [`StandardMetricsSpanProcessor`] to pre-aggregate them from all spans before sampling (requires
+//! the `trace` and `metrics` features).
 //!
 //! [`Aggregation`]: https://docs.rs/opentelemetry/0.20.0/opentelemetry/sdk/metrics/data/trait.Aggregation.html
 #![doc(html_root_url = "https://docs.rs/opentelemetry-application-insights/0.41.0")]
@@ -345,15 +465,38 @@ async fn main() {
 
 mod connection_string;
 mod convert;
+#[cfg(feature = "metrics")]
+mod cumulative;
+#[cfg(any(feature = "trace", feature = "logs"))]
+mod dedup;
+#[cfg(any(feature = "trace", feature = "logs"))]
+mod exception;
+#[cfg(feature = "logs")]
+mod log_filter;
 #[cfg(feature = "logs")]
 mod logs;
+#[cfg(any(feature = "trace", feature = "logs"))]
+mod measurements;
+#[cfg(feature = "metrics")]
+mod metric_naming;
 #[cfg(feature = "metrics")]
 mod metrics;
 mod models;
+#[cfg(feature = "trace")]
+mod operation_context;
 #[cfg(feature = "live-metrics")]
 mod quick_pulse;
 #[cfg(doctest)]
 mod readme_test;
+mod resource;
+#[cfg(any(feature = "trace", feature = "logs"))]
+mod stack_trace;
+#[cfg(all(feature = "trace", feature = "metrics"))]
+mod standard_metrics;
+#[cfg(feature = "trace")]
+mod success;
+#[cfg(feature = "trace")]
+mod synthetic;
 mod tags;
 #[cfg(feature = "trace")]
 mod trace;
@@ -364,14 +507,39 @@ mod uploader_quick_pulse;
 #[cfg(feature = "live-metrics")]
 use connection_string::DEFAULT_LIVE_ENDPOINT;
 use connection_string::{ConnectionString, DEFAULT_BREEZE_ENDPOINT};
+#[cfg(feature = "metrics")]
+use cumulative::CumulativeToDelta;
+#[cfg(any(feature = "trace", feature = "logs"))]
+use dedup::Deduplicator;
+#[cfg(feature = "trace")]
+pub use exception::SpanExt;
+#[cfg(feature = "logs")]
+pub use log_filter::LogFilter;
+#[cfg(any(feature = "trace", feature = "logs"))]
+pub use measurements::MeasurementsPolicy;
+#[cfg(feature = "metrics")]
+pub use metric_naming::MetricNamingPolicy;
 pub use models::context_tag_keys::attrs;
 pub use opentelemetry_http::HttpClient;
 use opentelemetry_sdk::error::OTelSdkError;
+#[cfg(feature = "metrics")]
+use opentelemetry_sdk::metrics::Temporality;
 use opentelemetry_sdk::ExportError;
 #[cfg(any(feature = "trace", feature = "logs"))]
 use opentelemetry_sdk::Resource;
+#[cfg(feature = "trace")]
+pub use operation_context::OperationContextSpanProcessor;
+#[cfg(feature = "trace")]
+use operation_context::OperationContexts;
 #[cfg(feature = "live-metrics")]
 pub use quick_pulse::LiveMetricsSpanProcessor;
+pub use resource::{AzureResourceDetector, AzureVmResourceDetector};
+#[cfg(all(feature = "trace", feature = "metrics"))]
+pub use standard_metrics::StandardMetricsSpanProcessor;
+#[cfg(any(feature = "trace", feature = "logs"))]
+use std::borrow::Cow;
+#[cfg(all(feature = "trace", feature = "metrics"))]
+use std::sync::atomic::AtomicBool;
 use std::{
     convert::TryInto,
     error::Error as StdError,
@@ -379,9 +547,15 @@ use std::{
     sync::{Arc, Mutex},
     time::Duration,
 };
+#[cfg(feature = "trace")]
+pub use success::SuccessPolicy;
+#[cfg(feature = "trace")]
+pub use synthetic::SyntheticSourceDetector;
 #[cfg(feature = "live-metrics")]
 use uploader_quick_pulse::PostOrPing;
 
+type RetryNotify = Arc<Mutex<dyn FnMut(&Error, Duration) + Send + 'static>>;
+
 /// Application Insights span exporter
 #[derive(Clone)]
 pub struct Exporter<C> {
@@ -392,13 +566,39 @@ pub struct Exporter<C> {
     #[cfg(feature = "live-metrics")]
     live_ping_endpoint: http::Uri,
     instrumentation_key: String,
-    retry_notify: Option<Arc<Mutex<dyn FnMut(&Error, Duration) + Send + 'static>>>,
+    retry_notify: Option<RetryNotify>,
     #[cfg(feature = "trace")]
     sample_rate: f64,
+    #[cfg(feature = "trace")]
+    success_policy: success::SharedSuccessPolicy,
+    #[cfg(feature = "trace")]
+    operation_contexts: Arc<OperationContexts>,
+    #[cfg(feature = "trace")]
+    synthetic_source_detector: synthetic::SharedSyntheticSourceDetector,
+    #[cfg(feature = "trace")]
+    span_attributes_in_events: Vec<Cow<'static, str>>,
     #[cfg(any(feature = "trace", feature = "logs"))]
     resource: Resource,
     #[cfg(any(feature = "trace", feature = "logs"))]
     resource_attributes_in_events_and_logs: bool,
+    #[cfg(any(feature = "trace", feature = "logs"))]
+    measurements_policy: MeasurementsPolicy,
+    #[cfg(feature = "logs")]
+    log_body_flattening_prefix: Option<Cow<'static, str>>,
+    #[cfg(feature = "logs")]
+    log_filter: Option<LogFilter>,
+    #[cfg(any(feature = "trace", feature = "logs"))]
+    deduplicator: Option<Arc<Deduplicator>>,
+    #[cfg(feature = "metrics")]
+    histogram_percentiles: Vec<f64>,
+    #[cfg(feature = "metrics")]
+    metric_naming_policy: MetricNamingPolicy,
+    #[cfg(feature = "metrics")]
+    temporality: Temporality,
+    #[cfg(feature = "metrics")]
+    cumulative_to_delta: CumulativeToDelta,
+    #[cfg(all(feature = "trace", feature = "metrics"))]
+    standard_metrics: Arc<AtomicBool>,
 }
 
 impl<C: Debug> Debug for Exporter<C> {
@@ -409,12 +609,37 @@ impl<C: Debug> Debug for Exporter<C> {
             .field("track_endpoint", &self.track_endpoint)
             .field("instrumentation_key", &self.instrumentation_key);
         #[cfg(feature = "trace")]
-        debug.field("sample_rate", &self.sample_rate);
+        debug
+            .field("sample_rate", &self.sample_rate)
+            .field("success_policy", &self.success_policy)
+            .field("synthetic_source_detector", &self.synthetic_source_detector)
+            .field("span_attributes_in_events", &self.span_attributes_in_events);
         #[cfg(any(feature = "trace", feature = "logs"))]
-        debug.field("resource", &self.resource).field(
-            "resource_attributes_in_events_and_logs",
-            &self.resource_attributes_in_events_and_logs,
-        );
+        debug
+            .field("resource", &self.resource)
+            .field(
+                "resource_attributes_in_events_and_logs",
+                &self.resource_attributes_in_events_and_logs,
+            )
+            .field("measurements_policy", &self.measurements_policy)
+            .field(
+                "deduplication_window",
+                &self.deduplicator.as_ref().map(|x| x.window()),
+            );
+        #[cfg(feature = "logs")]
+        debug
+            .field(
+                "log_body_flattening_prefix",
+                &self.log_body_flattening_prefix,
+            )
+            .field("log_filter", &self.log_filter);
+        #[cfg(feature = "metrics")]
+        debug
+            .field("histogram_percentiles", &self.histogram_percentiles)
+            .field("metric_naming_policy", &self.metric_naming_policy)
+            .field("temporality", &self.temporality);
+        #[cfg(all(feature = "trace", feature = "metrics"))]
+        debug.field("standard_metrics", &self.standard_metrics);
         #[cfg(feature = "live-metrics")]
         debug
             .field("live_post_endpoint", &self.live_post_endpoint)
@@ -446,10 +671,36 @@ impl<C> Exporter<C> {
             retry_notify: None,
             #[cfg(feature = "trace")]
             sample_rate: 100.0,
+            #[cfg(feature = "trace")]
+            success_policy: Default::default(),
+            #[cfg(feature = "trace")]
+            operation_contexts: Arc::new(OperationContexts::default()),
+            #[cfg(feature = "trace")]
+            synthetic_source_detector: Default::default(),
+            #[cfg(feature = "trace")]
+            span_attributes_in_events: Vec::new(),
             #[cfg(any(feature = "trace", feature = "logs"))]
             resource: Resource::builder_empty().build(),
             #[cfg(any(feature = "trace", feature = "logs"))]
             resource_attributes_in_events_and_logs: false,
+            #[cfg(any(feature = "trace", feature = "logs"))]
+            measurements_policy: MeasurementsPolicy::default(),
+            #[cfg(feature = "logs")]
+            log_body_flattening_prefix: None,
+            #[cfg(feature = "logs")]
+            log_filter: None,
+            #[cfg(any(feature = "trace", feature = "logs"))]
+            deduplicator: None,
+            #[cfg(feature = "metrics")]
+            histogram_percentiles: Vec::new(),
+            #[cfg(feature = "metrics")]
+            metric_naming_policy: MetricNamingPolicy::default(),
+            #[cfg(feature = "metrics")]
+            temporality: Temporality::Delta,
+            #[cfg(feature = "metrics")]
+            cumulative_to_delta: CumulativeToDelta::default(),
+            #[cfg(all(feature = "trace", feature = "metrics"))]
+            standard_metrics: Arc::default(),
         }
     }
 
@@ -486,10 +737,36 @@ impl<C> Exporter<C> {
             retry_notify: None,
             #[cfg(feature = "trace")]
             sample_rate: 100.0,
+            #[cfg(feature = "trace")]
+            success_policy: Default::default(),
+            #[cfg(feature = "trace")]
+            operation_contexts: Arc::new(OperationContexts::default()),
+            #[cfg(feature = "trace")]
+            synthetic_source_detector: Default::default(),
+            #[cfg(feature = "trace")]
+            span_attributes_in_events: Vec::new(),
             #[cfg(any(feature = "trace", feature = "logs"))]
             resource: Resource::builder_empty().build(),
             #[cfg(any(feature = "trace", feature = "logs"))]
             resource_attributes_in_events_and_logs: false,
+            #[cfg(any(feature = "trace", feature = "logs"))]
+            measurements_policy: MeasurementsPolicy::default(),
+            #[cfg(feature = "logs")]
+            log_body_flattening_prefix: None,
+            #[cfg(feature = "logs")]
+            log_filter: None,
+            #[cfg(any(feature = "trace", feature = "logs"))]
+            deduplicator: None,
+            #[cfg(feature = "metrics")]
+            histogram_percentiles: Vec::new(),
+            #[cfg(feature = "metrics")]
+            metric_naming_policy: MetricNamingPolicy::default(),
+            #[cfg(feature = "metrics")]
+            temporality: Temporality::Delta,
+            #[cfg(feature = "metrics")]
+            cumulative_to_delta: CumulativeToDelta::default(),
+            #[cfg(all(feature = "trace", feature = "metrics"))]
+            standard_metrics: Arc::default(),
         })
     }
 
@@ -528,6 +805,53 @@ impl<C> Exporter<C> {
         self
     }
 
+    /// Set the policy that decides whether requests and dependencies were successful.
+    ///
+    /// The policy is shared with all clones of the exporter and the span processors created from
+    /// them, like the `LiveMetricsSpanProcessor` and the `StandardMetricsSpanProcessor`.
+    ///
+    /// Default: only the span status is considered, see [`SuccessPolicy`].
+    #[cfg(feature = "trace")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "trace")))]
+    pub fn with_success_policy(self, success_policy: SuccessPolicy) -> Self {
+        self.success_policy.set(success_policy);
+        self
+    }
+
+    /// Set the detector for synthetic traffic, which sets the synthetic source
+    /// (`ai.operation.syntheticSource`) of requests.
+    ///
+    /// The detector is shared with all clones of the exporter and the span processors created
+    /// from them, like the [`OperationContextSpanProcessor`].
+    ///
+    /// Default: no detection.
+    #[cfg(feature = "trace")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "trace")))]
+    pub fn with_synthetic_source_detector(
+        self,
+        synthetic_source_detector: SyntheticSourceDetector,
+    ) -> Self {
+        self.synthetic_source_detector
+            .set(synthetic_source_detector);
+        self
+    }
+
+    /// Set span attributes, which should be included in the properties of the span's events.
+    ///
+    /// Attributes of the event itself take precedence. Context tags, like the user id or the
+    /// operation name, are always set on events.
+    ///
+    /// Default: none.
+    #[cfg(feature = "trace")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "trace")))]
+    pub fn with_span_attributes_in_events(
+        mut self,
+        keys: impl IntoIterator<Item = impl Into<Cow<'static, str>>>,
+    ) -> Self {
+        self.span_attributes_in_events = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
     /// Set whether resource attributes should be included in events.
     ///
     /// This affects both trace events and logs.
@@ -542,6 +866,119 @@ impl<C> Exporter<C> {
         self.resource_attributes_in_events_and_logs = resource_attributes_in_events_and_logs;
         self
     }
+
+    /// Set which attributes are sent as measurements instead of properties.
+    ///
+    /// This affects requests, dependencies, custom events and exceptions.
+    ///
+    /// Default: [`MeasurementsPolicy::Disabled`], i.e. all attributes are sent as properties.
+    #[cfg(any(feature = "trace", feature = "logs"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "trace", feature = "logs"))))]
+    pub fn with_measurements_policy(mut self, measurements_policy: MeasurementsPolicy) -> Self {
+        self.measurements_policy = measurements_policy;
+        self
+    }
+
+    /// Flatten log bodies, which are maps, into custom properties. The keys of the map are
+    /// prefixed with the given prefix, e.g. `"body."`. Attributes of the log record take
+    /// precedence over body entries with the same key.
+    ///
+    /// Default: disabled, i.e. the whole body is sent as JSON in the message.
+    #[cfg(feature = "logs")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "logs")))]
+    pub fn with_log_body_flattening(mut self, prefix: impl Into<Cow<'static, str>>) -> Self {
+        self.log_body_flattening_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Filter the log records before uploading them, e.g. to upload only warnings of noisy
+    /// dependencies. See [`LogFilter`] for the syntax of the directives.
+    ///
+    /// Default: disabled, i.e. all log records are uploaded.
+    #[cfg(feature = "logs")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "logs")))]
+    pub fn with_log_filter(mut self, log_filter: LogFilter) -> Self {
+        self.log_filter = Some(log_filter);
+        self
+    }
+
+    /// Deduplicate repeated messages and exceptions within the given time window.
+    ///
+    /// Messages (logs and span events) with the same message, severity level and operation name,
+    /// and exceptions with the same type, severity level and operation name are considered
+    /// repetitions. The message is compared after formatting, so messages which include
+    /// changing values, like ids or durations, are not deduplicated.
+    ///
+    /// The first occurrence is uploaded right away, the following ones in the window are only
+    /// counted. With the first upload after the window is over, a copy of the first occurrence is
+    /// uploaded with the custom property `occurrences`, the total number of occurrences in the
+    /// window. It keeps the operation id of the first occurrence. When the span or log exporter is
+    /// shut down, the summaries of all windows are uploaded right away, within the shutdown
+    /// timeout. All clones of the exporter share the windows.
+    ///
+    /// Default: disabled, i.e. all messages and exceptions are uploaded.
+    #[cfg(any(feature = "trace", feature = "logs"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "trace", feature = "logs"))))]
+    pub fn with_deduplication_window(mut self, window: Duration) -> Self {
+        self.deduplicator = Some(Arc::new(Deduplicator::new(window)));
+        self
+    }
+
+    /// Send estimated percentiles of histograms as additional metrics named
+    /// `<name>_p<percentile>`, e.g. `http.server.request.duration_p95` for the percentile `95.0`.
+    /// Percentiles are between 0 and 100.
+    ///
+    /// The percentiles are estimated from the bucket counts, assuming the values are evenly
+    /// distributed within each bucket. Their accuracy depends on the bucket boundaries.
+    ///
+    /// Default: no percentiles.
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+    pub fn with_histogram_percentiles(
+        mut self,
+        percentiles: impl IntoIterator<Item = f64>,
+    ) -> Self {
+        self.histogram_percentiles = percentiles.into_iter().collect();
+        self
+    }
+
+    /// Set the policy for the namespace and names of metrics.
+    ///
+    /// Default: metric names are unchanged and the default namespace is used.
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+    pub fn with_metric_naming_policy(mut self, metric_naming_policy: MetricNamingPolicy) -> Self {
+        self.metric_naming_policy = metric_naming_policy;
+        self
+    }
+
+    /// Set the temporality the exporter asks the meter provider for.
+    ///
+    /// Application Insights expects deltas, so cumulative sums and histograms are converted to
+    /// deltas by the exporter. It remembers the last value of each series (instrumentation scope,
+    /// metric and attribute set) for this, detects resets by a new start time or a lower value,
+    /// and forgets series which haven't been exported for 10 exports. Min and max of converted
+    /// histograms are not sent. Non-monotonic sums (up-down counters) are sent as they are.
+    ///
+    /// The last values are remembered before the upload, so if an upload fails, the deltas of
+    /// that interval are lost. Each clone of the exporter starts without any remembered values,
+    /// so clones can be used with different readers.
+    ///
+    /// - [`Temporality::Delta`]: delta for all instruments except up-down counters.
+    /// - [`Temporality::LowMemory`]: delta only for synchronous counters and histograms.
+    /// - [`Temporality::Cumulative`]: cumulative for all instruments, e.g. to share views with
+    ///   pull-based readers.
+    ///
+    /// The temporality can't be chosen per instrument kind, because the SDK only asks exporters
+    /// for one of these presets.
+    ///
+    /// Default: [`Temporality::Delta`].
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+    pub fn with_temporality(mut self, temporality: Temporality) -> Self {
+        self.temporality = temporality;
+        self
+    }
 }
 
 fn append_v2_track(uri: impl ToString) -> http::Uri {
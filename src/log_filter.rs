@@ -0,0 +1,248 @@
+use opentelemetry::{logs::Severity, InstrumentationScope};
+use opentelemetry_sdk::logs::SdkLogRecord;
+use std::{
+    error::Error as StdError,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+
+#[derive(thiserror::Error, Debug)]
+enum ParseError {
+    #[error("invalid level \"{0}\" in directive \"{1}\"")]
+    InvalidLevel(String, String),
+}
+
+/// Filter for the log records the exporter uploads, configured with directives like the ones of
+/// `RUST_LOG` or `env_logger`.
+///
+/// The directives are separated by commas and have one of the forms:
+///
+/// - `level`: the level for all targets, e.g. `warn`.
+/// - `target=level`: the level for targets starting with `target`, e.g. `hyper=error`.
+/// - `target`: all levels for targets starting with `target`.
+///
+/// The levels are `trace`, `debug`, `info`, `warn`, `error` and `off`, ignoring case. The target
+/// of a log record is its target, as set by the `log` and `tracing` appenders, or the name of its
+/// instrumentation scope. The directive with the longest matching target applies. Log records
+/// without a matching directive are dropped, and an empty filter uploads only errors. Log records
+/// without a severity number are always uploaded.
+///
+/// The filter can be cloned and reloaded at runtime, e.g. from a configuration endpoint. All
+/// clones share the directives and the number of filtered log records.
+///
+/// ```
+/// use opentelemetry_application_insights::LogFilter;
+///
+/// let filter = LogFilter::parse("info,hyper=warn,my_app=trace").expect("valid directives");
+/// # let http_client = reqwest::blocking::Client::new();
+/// let exporter = opentelemetry_application_insights::Exporter::new_from_connection_string(
+///     "InstrumentationKey=00000000-0000-0000-0000-000000000000",
+///     http_client,
+/// )
+/// .expect("valid connection string")
+/// .with_log_filter(filter.clone());
+///
+/// // later
+/// filter.reload("debug").expect("valid directives");
+/// println!("{} log records filtered", filter.filtered_count());
+/// ```
+#[derive(Clone)]
+pub struct LogFilter {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    directives: RwLock<Vec<Directive>>,
+    filtered_count: AtomicU64,
+}
+
+#[derive(Debug, PartialEq)]
+struct Directive {
+    target: Option<String>,
+    /// Lowest enabled severity, or `None` if the target is turned off.
+    min_severity: Option<Severity>,
+}
+
+impl Debug for LogFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogFilter")
+            .field("directives", &*self.inner.directives.read().unwrap())
+            .field("filtered_count", &self.filtered_count())
+            .finish()
+    }
+}
+
+impl LogFilter {
+    /// Create a new filter from comma-separated directives.
+    pub fn parse(directives: &str) -> Result<Self, Box<dyn StdError + Send + Sync + 'static>> {
+        Ok(Self {
+            inner: Arc::new(Inner {
+                directives: RwLock::new(parse_directives(directives)?),
+                filtered_count: AtomicU64::new(0),
+            }),
+        })
+    }
+
+    /// Replace the directives of this filter and all its clones. The directives are left
+    /// unchanged if they can't be parsed.
+    pub fn reload(
+        &self,
+        directives: &str,
+    ) -> Result<(), Box<dyn StdError + Send + Sync + 'static>> {
+        let directives = parse_directives(directives)?;
+        *self.inner.directives.write().unwrap() = directives;
+        Ok(())
+    }
+
+    /// Number of log records dropped by this filter and all its clones.
+    pub fn filtered_count(&self) -> u64 {
+        self.inner.filtered_count.load(Ordering::Relaxed)
+    }
+
+    /// Whether the log record should be uploaded. Counts the log record if not.
+    pub(crate) fn filter(&self, record: &SdkLogRecord, scope: &InstrumentationScope) -> bool {
+        let severity = match record.severity_number() {
+            Some(severity) => severity,
+            None => return true,
+        };
+        let target = record
+            .target()
+            .map(|target| target.as_ref())
+            .unwrap_or_else(|| scope.name());
+        let enabled = self.enabled(target, severity);
+        if !enabled {
+            self.inner.filtered_count.fetch_add(1, Ordering::Relaxed);
+        }
+        enabled
+    }
+
+    fn enabled(&self, target: &str, severity: Severity) -> bool {
+        let directives = self.inner.directives.read().unwrap();
+        // Directives are sorted by target length, so the first match is the most specific one.
+        directives
+            .iter()
+            .find(|directive| match &directive.target {
+                Some(prefix) => target.starts_with(prefix.as_str()),
+                None => true,
+            })
+            .and_then(|directive| directive.min_severity)
+            .is_some_and(|min_severity| severity >= min_severity)
+    }
+}
+
+fn parse_directives(directives: &str) -> Result<Vec<Directive>, ParseError> {
+    let mut parsed = Vec::new();
+    for directive in directives
+        .split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+    {
+        let parsed_directive = match directive.split_once('=') {
+            Some((target, level)) => Directive {
+                target: Some(target.trim().to_string()).filter(|x| !x.is_empty()),
+                min_severity: parse_level(level.trim()).ok_or_else(|| {
+                    ParseError::InvalidLevel(level.trim().into(), directive.into())
+                })?,
+            },
+            None => match parse_level(directive) {
+                Some(min_severity) => Directive {
+                    target: None,
+                    min_severity,
+                },
+                None => Directive {
+                    target: Some(directive.to_string()),
+                    min_severity: Some(Severity::Trace),
+                },
+            },
+        };
+        // Later directives for the same target win.
+        parsed.retain(|x: &Directive| x.target != parsed_directive.target);
+        parsed.push(parsed_directive);
+    }
+    if parsed.is_empty() {
+        parsed.push(Directive {
+            target: None,
+            min_severity: Some(Severity::Error),
+        });
+    }
+    parsed.sort_by_key(|x| std::cmp::Reverse(x.target.as_ref().map_or(0, String::len)));
+    Ok(parsed)
+}
+
+/// Lowest severity of a level. `Some(None)` for `off`.
+fn parse_level(level: &str) -> Option<Option<Severity>> {
+    Some(Some(match level.to_ascii_lowercase().as_str() {
+        "off" => return Some(None),
+        "trace" => Severity::Trace,
+        "debug" => Severity::Debug,
+        "info" => Severity::Info,
+        "warn" => Severity::Warn,
+        "error" => Severity::Error,
+        _ => return None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::logs::{LogRecord as _, Logger as _, LoggerProvider as _};
+    use opentelemetry_sdk::logs::SdkLoggerProvider;
+
+    #[test]
+    fn levels_and_targets() {
+        let filter = LogFilter::parse("info, hyper=warn,hyper::client=off,my_app,noisy=ERROR")
+            .expect("valid directives");
+        assert!(filter.enabled("other", Severity::Info));
+        assert!(!filter.enabled("other", Severity::Debug4));
+        assert!(filter.enabled("hyper::server", Severity::Warn));
+        assert!(!filter.enabled("hyper::server", Severity::Info));
+        assert!(!filter.enabled("hyper::client::pool", Severity::Fatal));
+        assert!(filter.enabled("my_app::db", Severity::Trace));
+        assert!(!filter.enabled("noisy", Severity::Warn4));
+        assert!(filter.enabled("noisy", Severity::Error));
+        assert!(filter.enabled("noisy", Severity::Fatal));
+    }
+
+    #[test]
+    fn without_default_directive() {
+        let filter = LogFilter::parse("my_app=debug").expect("valid directives");
+        assert!(filter.enabled("my_app", Severity::Debug));
+        assert!(!filter.enabled("other", Severity::Fatal));
+
+        let filter = LogFilter::parse("").expect("valid directives");
+        assert!(filter.enabled("other", Severity::Error));
+        assert!(!filter.enabled("other", Severity::Warn));
+    }
+
+    #[test]
+    fn invalid_level() {
+        assert!(LogFilter::parse("hyper=loud").is_err());
+
+        let filter = LogFilter::parse("warn").expect("valid directives");
+        assert!(filter.reload("info,hyper=loud").is_err());
+        assert!(!filter.enabled("other", Severity::Info));
+    }
+
+    #[test]
+    fn reload_and_count() {
+        let filter = LogFilter::parse("warn").expect("valid directives");
+        let clone = filter.clone();
+        let provider = SdkLoggerProvider::builder().build();
+        let logger = provider.logger("my_app");
+        let scope = InstrumentationScope::builder("my_app").build();
+
+        let mut record = logger.create_log_record();
+        record.set_severity_number(Severity::Info);
+        assert!(!clone.filter(&record, &scope));
+        record.set_target("other");
+        filter.reload("other=info").expect("valid directives");
+        assert!(clone.filter(&record, &scope));
+
+        let record = logger.create_log_record();
+        assert!(clone.filter(&record, &scope), "no severity number");
+        assert_eq!(filter.filtered_count(), 1);
+    }
+}
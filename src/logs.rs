@@ -2,11 +2,15 @@ use crate::{
     convert::{
         attrs_map_to_properties, attrs_to_map, attrs_to_properties, time_to_string, AttrValue,
     },
-    models::{Data, Envelope, ExceptionData, ExceptionDetails, MessageData, SeverityLevel},
+    exception::{attrs_to_escaped_severity_level, attrs_to_exception_details},
+    models::{Data, Envelope, EventData, ExceptionData, MessageData, Properties, SeverityLevel},
     tags::get_tags_for_log,
-    Exporter,
+    Exporter, MeasurementsPolicy,
+};
+use opentelemetry::{
+    logs::{AnyValue, Severity},
+    InstrumentationScope,
 };
-use opentelemetry::{logs::Severity, InstrumentationScope};
 use opentelemetry_http::HttpClient;
 use opentelemetry_sdk::{
     error::OTelSdkResult,
@@ -14,7 +18,27 @@ use opentelemetry_sdk::{
     Resource,
 };
 use opentelemetry_semantic_conventions as semcov;
-use std::{sync::Arc, time::SystemTime};
+use std::{
+    borrow::Cow,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+/// Attribute used by the Azure Monitor exporters to send a log record as custom event.
+const MICROSOFT_CUSTOM_EVENT_NAME: &str = "microsoft.custom_event.name";
+
+/// Property for the name of the instrumentation scope, i.e. the log target or category. Named
+/// like the property of the .NET logger.
+const PROPERTY_CATEGORY_NAME: &str = "CategoryName";
+
+/// Property for the version of the instrumentation scope.
+const PROPERTY_CATEGORY_VERSION: &str = "CategoryVersion";
+
+/// Property for the body of a log record sent as custom event, which has no message field.
+const PROPERTY_EVENT_BODY: &str = "body";
+
+/// Property for the source code location in the format `<file>:<line>`.
+const PROPERTY_SOURCE_LOCATION: &str = "SourceLocation";
 
 fn is_exception(record: &SdkLogRecord) -> bool {
     record.attributes_iter().any(|(k, _)| {
@@ -23,6 +47,25 @@ fn is_exception(record: &SdkLogRecord) -> bool {
     })
 }
 
+fn get_event_name(record: &SdkLogRecord) -> Option<Cow<'_, str>> {
+    if let Some((_, name)) = record
+        .attributes_iter()
+        .find(|(k, _)| k.as_str() == MICROSOFT_CUSTOM_EVENT_NAME)
+    {
+        return Some(name.as_str());
+    }
+    record
+        .event_name()
+        .filter(|name| !is_generated_tracing_event_name(name))
+        .map(Into::into)
+}
+
+/// The `tracing` crate names events `event <file>:<line>` unless they are given a name. These
+/// names are not meant to be event names.
+fn is_generated_tracing_event_name(name: &str) -> bool {
+    name.starts_with("event ") && name.contains(':')
+}
+
 impl<C> Exporter<C> {
     fn create_envelope_for_log(
         &self,
@@ -33,17 +76,44 @@ impl<C> Exporter<C> {
         } else {
             None
         };
-        let (data, name) = if is_exception(record) {
+        let (mut data, name) = if is_exception(record) {
             (
                 Data::Exception(RecordAndResource(record, event_resource).into()),
                 "Microsoft.ApplicationInsights.Exception",
             )
+        } else if get_event_name(record).is_some() {
+            (
+                Data::Event(RecordAndResource(record, event_resource).into()),
+                "Microsoft.ApplicationInsights.Event",
+            )
         } else {
             (
                 Data::Message(RecordAndResource(record, event_resource).into()),
                 "Microsoft.ApplicationInsights.Message",
             )
         };
+        if let Some((properties, measurements)) = data.properties_and_measurements_mut() {
+            self.measurements_policy.apply(
+                &attrs_to_map(record.attributes_iter()),
+                properties,
+                measurements,
+            );
+        }
+        if let Some(properties) = log_properties_mut(&mut data) {
+            add_category(instrumentation_scope, properties);
+            add_source_location(record, properties);
+            if let Some(prefix) = &self.log_body_flattening_prefix {
+                flatten_body(record, prefix, properties);
+            }
+        }
+
+        #[allow(unused_mut)]
+        let mut tags = get_tags_for_log(record, instrumentation_scope, &self.resource);
+        #[cfg(feature = "trace")]
+        if let Some(trace_context) = record.trace_context() {
+            self.operation_contexts
+                .apply_to_tags(trace_context.trace_id, &mut tags);
+        }
 
         Envelope {
             name,
@@ -56,11 +126,7 @@ impl<C> Exporter<C> {
             .into(),
             sample_rate: None,
             i_key: Some(self.instrumentation_key.clone().into()),
-            tags: Some(get_tags_for_log(
-                record,
-                instrumentation_scope,
-                &self.resource,
-            )),
+            tags: Some(tags),
             data: Some(data),
         }
     }
@@ -79,10 +145,19 @@ where
         let endpoint = Arc::clone(&self.track_endpoint);
         let envelopes: Vec<_> = batch
             .iter()
+            .filter(|(record, scope)| {
+                self.log_filter
+                    .as_ref()
+                    .is_none_or(|log_filter| log_filter.filter(record, scope))
+            })
             .map(|log| self.create_envelope_for_log(log))
             .collect();
+        let envelopes = self.deduplicate(envelopes);
 
         async move {
+            if envelopes.is_empty() {
+                return Ok(());
+            }
             crate::uploader::send(
                 client.as_ref(),
                 endpoint.as_ref(),
@@ -94,6 +169,10 @@ where
         }
     }
 
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.flush_deduplication(timeout)
+    }
+
     fn set_resource(&mut self, resource: &Resource) {
         self.resource = resource.clone();
     }
@@ -126,29 +205,137 @@ impl From<Severity> for SeverityLevel {
     }
 }
 
+fn log_properties_mut(data: &mut Data) -> Option<&mut Option<Properties>> {
+    match data {
+        Data::Event(data) => Some(&mut data.properties),
+        Data::Exception(data) => Some(&mut data.properties),
+        Data::Message(data) => Some(&mut data.properties),
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+/// Add the name and version of the instrumentation scope to the properties.
+fn add_category(scope: &InstrumentationScope, properties: &mut Option<Properties>) {
+    if scope.name().is_empty() {
+        return;
+    }
+    let properties = properties.get_or_insert_with(Properties::new);
+    properties
+        .entry(PROPERTY_CATEGORY_NAME.into())
+        .or_insert_with(|| scope.name().into());
+    if let Some(version) = scope.version() {
+        properties
+            .entry(PROPERTY_CATEGORY_VERSION.into())
+            .or_insert_with(|| version.into());
+    }
+}
+
+/// Replace the code file path and line number attributes with a single source location property.
+fn add_source_location(record: &SdkLogRecord, properties: &mut Option<Properties>) {
+    #[allow(deprecated)]
+    let (deprecated_file_path, deprecated_line_number) = (
+        semcov::attribute::CODE_FILEPATH,
+        semcov::attribute::CODE_LINENO,
+    );
+    let attrs = attrs_to_map(record.attributes_iter());
+    let file_path = match attrs
+        .get(semcov::attribute::CODE_FILE_PATH)
+        .or_else(|| attrs.get(deprecated_file_path))
+    {
+        Some(file_path) => file_path.as_str(),
+        None => return,
+    };
+    let source_location = match attrs
+        .get(semcov::attribute::CODE_LINE_NUMBER)
+        .or_else(|| attrs.get(deprecated_line_number))
+    {
+        Some(line_number) => format!("{}:{}", file_path, line_number.as_str()),
+        None => file_path.into_owned(),
+    };
+
+    let properties = properties.get_or_insert_with(Properties::new);
+    for key in [
+        semcov::attribute::CODE_FILE_PATH,
+        deprecated_file_path,
+        semcov::attribute::CODE_LINE_NUMBER,
+        deprecated_line_number,
+    ] {
+        properties.remove(&key.into());
+    }
+    properties.insert(PROPERTY_SOURCE_LOCATION.into(), source_location.into());
+}
+
+/// Add the entries of a map body to the properties.
+fn flatten_body(record: &SdkLogRecord, prefix: &str, properties: &mut Option<Properties>) {
+    let map = match record.body() {
+        Some(AnyValue::Map(map)) => map,
+        _ => return,
+    };
+    let properties = properties.get_or_insert_with(Properties::new);
+    for (key, value) in map.iter() {
+        properties
+            .entry(format!("{}{}", prefix, key).into())
+            .or_insert_with(|| (value as &dyn AttrValue).as_str().into());
+    }
+}
+
+/// Severity level from the severity number or, if it is missing, from common severity texts.
+fn get_severity_level(record: &SdkLogRecord) -> Option<SeverityLevel> {
+    if let Some(severity) = record.severity_number() {
+        return Some(severity.into());
+    }
+    let text = record.severity_text()?.to_ascii_lowercase();
+    Some(match text.as_str() {
+        "trace" | "verbose" | "debug" | "fine" | "finer" | "finest" => SeverityLevel::Verbose,
+        "info" | "information" | "informational" | "notice" => SeverityLevel::Information,
+        "warn" | "warning" => SeverityLevel::Warning,
+        "error" | "err" => SeverityLevel::Error,
+        "fatal" | "critical" | "crit" | "alert" | "emerg" | "emergency" | "panic" => {
+            SeverityLevel::Critical
+        }
+        _ => return None,
+    })
+}
+
 struct RecordAndResource<'a>(&'a SdkLogRecord, Option<&'a Resource>);
 
 impl From<RecordAndResource<'_>> for ExceptionData {
     fn from(RecordAndResource(record, resource): RecordAndResource) -> ExceptionData {
         let mut attrs = attrs_to_map(record.attributes_iter());
-        let exception = ExceptionDetails {
-            type_name: attrs
-                .remove(semcov::trace::EXCEPTION_TYPE)
-                .map(Into::into)
-                .unwrap_or_else(|| "".into()),
-            message: attrs
-                .remove(semcov::trace::EXCEPTION_MESSAGE)
-                .map(Into::into)
-                .unwrap_or_else(|| "".into()),
-            stack: attrs
-                .remove(semcov::trace::EXCEPTION_STACKTRACE)
-                .map(Into::into),
-        };
+        let exceptions = attrs_to_exception_details(&mut attrs, "", "");
+        let escaped_severity_level = attrs_to_escaped_severity_level(&mut attrs);
         ExceptionData {
             ver: 2,
-            exceptions: vec![exception],
-            severity_level: record.severity_number().map(Into::into),
+            exceptions,
+            severity_level: get_severity_level(record).or(escaped_severity_level),
             properties: attrs_map_to_properties(attrs, resource),
+            measurements: None,
+        }
+    }
+}
+
+impl From<RecordAndResource<'_>> for EventData {
+    fn from(RecordAndResource(record, resource): RecordAndResource) -> EventData {
+        let name = get_event_name(record)
+            .map(|name| name.into_owned().into())
+            .unwrap_or_else(|| "<no name>".into());
+        let mut attrs = attrs_to_map(record.attributes_iter());
+        attrs.remove(MICROSOFT_CUSTOM_EVENT_NAME);
+        let mut properties = attrs_map_to_properties(attrs.clone(), resource);
+        if let Some(body) = record.body() {
+            properties
+                .get_or_insert_with(Properties::new)
+                .entry(PROPERTY_EVENT_BODY.into())
+                .or_insert_with(|| (body as &dyn AttrValue).as_str().into());
+        }
+        let mut measurements = None;
+        MeasurementsPolicy::Numeric.apply(&attrs, &mut properties, &mut measurements);
+        EventData {
+            ver: 2,
+            name,
+            properties,
+            measurements,
         }
     }
 }
@@ -157,7 +344,7 @@ impl From<RecordAndResource<'_>> for MessageData {
     fn from(RecordAndResource(record, resource): RecordAndResource) -> MessageData {
         MessageData {
             ver: 2,
-            severity_level: record.severity_number().map(Into::into),
+            severity_level: get_severity_level(record),
             message: record
                 .body()
                 .as_ref()
@@ -0,0 +1,173 @@
+use crate::{
+    convert::AttrValue,
+    models::{Measurements, Properties},
+};
+use opentelemetry_semantic_conventions as semcov;
+use std::{borrow::Cow, collections::HashMap};
+
+/// Prefix of semantic convention keys for GenAI token usage. Numeric attributes with this prefix
+/// are always sent as measurements, regardless of the [`MeasurementsPolicy`].
+const GEN_AI_USAGE_PREFIX: &str = "gen_ai.usage.";
+
+/// Numeric attributes, which are mapped to fields of the telemetry (like the response code or the
+/// port of the target) and therefore don't become measurements with [`MeasurementsPolicy::Numeric`].
+#[allow(deprecated)]
+const MAPPED_NUMERIC_ATTRIBUTES: &[&str] = &[
+    semcov::attribute::HTTP_RESPONSE_STATUS_CODE,
+    semcov::attribute::HTTP_STATUS_CODE,
+    semcov::attribute::SERVER_PORT,
+    semcov::attribute::NETWORK_PEER_PORT,
+    semcov::attribute::NET_HOST_PORT,
+    semcov::attribute::NET_PEER_PORT,
+    semcov::attribute::NET_SOCK_PEER_PORT,
+    #[cfg(feature = "trace")]
+    crate::trace::DEPRECATED_SERVER_SOCKET_PORT,
+    semcov::attribute::RPC_GRPC_STATUS_CODE,
+    #[cfg(feature = "trace")]
+    crate::trace::MESSAGING_MESSAGE_ENQUEUED_TIME,
+    semcov::attribute::CODE_LINE_NUMBER,
+    semcov::attribute::CODE_LINENO,
+];
+
+/// Policy that decides which attributes are sent as measurements instead of properties.
+///
+/// Application Insights can aggregate measurements in metrics charts, which isn't possible for
+/// properties. Requests, dependencies, custom events and exceptions support measurements. Only
+/// attributes with integer or floating point values can become measurements. GenAI token usage
+/// (`gen_ai.usage.*`) is always sent as measurements.
+///
+/// ```
+/// use opentelemetry_application_insights::MeasurementsPolicy;
+///
+/// let policy = MeasurementsPolicy::Keys(vec!["db.response.returned_rows".into()]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum MeasurementsPolicy {
+    /// Send all attributes as properties. Numbers are converted to strings.
+    #[default]
+    Disabled,
+    /// Send all attributes with numeric values as measurements, except the ones which are mapped
+    /// to fields of the telemetry, like `http.response.status_code` or `server.port`.
+    Numeric,
+    /// Send attributes with the given keys as measurements, if their values are numeric.
+    Keys(Vec<Cow<'static, str>>),
+}
+
+impl MeasurementsPolicy {
+    /// Move attributes matching this policy (and GenAI token usage) from the properties into the
+    /// measurements.
+    pub(crate) fn apply(
+        &self,
+        attrs: &HashMap<&str, &dyn AttrValue>,
+        properties: &mut Option<Properties>,
+        measurements: &mut Option<Measurements>,
+    ) {
+        for (&key, value) in attrs {
+            if !self.is_measurement(key) {
+                continue;
+            }
+            if let Some(value) = value.as_f64() {
+                if let Some(properties) = properties.as_mut() {
+                    properties.remove(&key.into());
+                }
+                measurements
+                    .get_or_insert_with(Measurements::new)
+                    .insert(key.into(), value);
+            }
+        }
+
+        if properties.as_ref().is_some_and(|x| x.is_empty()) {
+            *properties = None;
+        }
+    }
+
+    fn is_measurement(&self, key: &str) -> bool {
+        if key.starts_with(GEN_AI_USAGE_PREFIX) {
+            return true;
+        }
+        if key.starts_with("_MS.") {
+            return false;
+        }
+        match self {
+            MeasurementsPolicy::Disabled => false,
+            MeasurementsPolicy::Numeric => !MAPPED_NUMERIC_ATTRIBUTES.contains(&key),
+            MeasurementsPolicy::Keys(keys) => keys.iter().any(|k| k == key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::attrs_to_map;
+    use opentelemetry::KeyValue;
+
+    fn apply(policy: MeasurementsPolicy) -> (Option<Properties>, Option<Measurements>) {
+        let attributes = [
+            KeyValue::new("count", 3),
+            KeyValue::new("ratio", 0.5),
+            KeyValue::new("name", "x"),
+            KeyValue::new(semcov::trace::HTTP_RESPONSE_STATUS_CODE, 200),
+            KeyValue::new(semcov::attribute::GEN_AI_USAGE_INPUT_TOKENS, 100),
+        ];
+        let attrs = attrs_to_map(attributes.iter());
+        let mut properties = Some(
+            attrs
+                .iter()
+                .map(|(&k, v)| (k.into(), v.as_str().into()))
+                .collect(),
+        );
+        let mut measurements = None;
+        policy.apply(&attrs, &mut properties, &mut measurements);
+        (properties, measurements)
+    }
+
+    fn keys<V>(
+        map: Option<std::collections::BTreeMap<crate::models::LimitedLenString<150>, V>>,
+    ) -> Vec<String> {
+        map.into_iter()
+            .flatten()
+            .map(|(k, _)| k.as_ref().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn disabled() {
+        let (properties, measurements) = apply(MeasurementsPolicy::Disabled);
+        assert_eq!(
+            keys(properties),
+            vec!["count", "http.response.status_code", "name", "ratio"]
+        );
+        assert_eq!(
+            keys(measurements),
+            vec!["gen_ai.usage.input_tokens"],
+            "always a measurement"
+        );
+    }
+
+    #[test]
+    fn numeric() {
+        let (properties, measurements) = apply(MeasurementsPolicy::Numeric);
+        assert_eq!(keys(properties), vec!["http.response.status_code", "name"]);
+        assert_eq!(
+            keys(measurements),
+            vec!["count", "gen_ai.usage.input_tokens", "ratio"]
+        );
+    }
+
+    #[test]
+    fn allow_listed_keys() {
+        let (properties, measurements) = apply(MeasurementsPolicy::Keys(vec![
+            "count".into(),
+            "name".into(),
+        ]));
+        assert_eq!(
+            keys(properties),
+            vec!["http.response.status_code", "name", "ratio"]
+        );
+        assert_eq!(
+            keys(measurements),
+            vec!["count", "gen_ai.usage.input_tokens"]
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use opentelemetry::InstrumentationScope;
+use opentelemetry_sdk::metrics::data::Metric;
+use std::borrow::Cow;
+
+/// Maximum length of metric names in Application Insights.
+const MAX_NAME_LEN: usize = 1024;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Namespace {
+    Default,
+    Scope,
+    Fixed(Cow<'static, str>),
+}
+
+/// Policy for the namespace and names of metrics in Application Insights.
+///
+/// By default metrics are sent with their name unchanged to the default namespace
+/// (`azure.applicationinsights`), where Metrics Explorer lists them as custom metrics. Use a
+/// namespace to separate the metrics of different teams or services.
+///
+/// ```
+/// use opentelemetry_application_insights::MetricNamingPolicy;
+///
+/// // `http.server.request.duration` with unit `s` becomes `http_server_request_duration_s` in the
+/// // namespace of the instrumentation scope, e.g. `my-library`.
+/// let policy = MetricNamingPolicy::new()
+///     .with_scope_namespace()
+///     .with_unit_suffix(true)
+///     .with_sanitization(true);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricNamingPolicy {
+    namespace: Namespace,
+    unit_suffix: bool,
+    sanitization: bool,
+    max_name_len: usize,
+}
+
+impl Default for MetricNamingPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetricNamingPolicy {
+    /// Create a new policy, which keeps the metric names unchanged and uses the default namespace.
+    pub fn new() -> Self {
+        Self {
+            namespace: Namespace::Default,
+            unit_suffix: false,
+            sanitization: false,
+            max_name_len: MAX_NAME_LEN,
+        }
+    }
+
+    /// Use the given namespace for all metrics.
+    pub fn with_namespace(mut self, namespace: impl Into<Cow<'static, str>>) -> Self {
+        self.namespace = Namespace::Fixed(namespace.into());
+        self
+    }
+
+    /// Use the name of the instrumentation scope (meter) of each metric as its namespace. Metrics
+    /// of meters without a name use the default namespace.
+    pub fn with_scope_namespace(mut self) -> Self {
+        self.namespace = Namespace::Scope;
+        self
+    }
+
+    /// Append the unit of the metric to its name, e.g. `request.duration_ms`. Dimensionless units
+    /// (`1`) and annotations in curly braces (like `{request}`) are not appended.
+    ///
+    /// Default: false.
+    pub fn with_unit_suffix(mut self, unit_suffix: bool) -> Self {
+        self.unit_suffix = unit_suffix;
+        self
+    }
+
+    /// Replace all characters except ASCII letters, digits, `_`, `-` and `/` in names and
+    /// namespaces with `_`, except for `µ`, which becomes `u`. Repeated `_` are collapsed into
+    /// one, and trailing `_` are removed.
+    ///
+    /// Default: false.
+    pub fn with_sanitization(mut self, sanitization: bool) -> Self {
+        self.sanitization = sanitization;
+        self
+    }
+
+    /// Truncate names to the given number of characters. Names of estimated percentiles (see
+    /// [`Exporter::with_histogram_percentiles`](crate::Exporter::with_histogram_percentiles)) keep
+    /// their percentile suffix, and the name before it is truncated instead.
+    ///
+    /// Default: 1024, the limit of Application Insights.
+    pub fn with_max_name_len(mut self, max_name_len: usize) -> Self {
+        self.max_name_len = max_name_len.min(MAX_NAME_LEN);
+        self
+    }
+
+    pub(crate) fn name(&self, metric: &Metric) -> String {
+        self.name_with_suffix(metric.name(), metric.unit(), "")
+    }
+
+    /// Name of an estimated percentile of a histogram, `<name>_p<percentile>`.
+    pub(crate) fn percentile_name(&self, metric: &Metric, percentile: f64) -> String {
+        self.name_with_suffix(metric.name(), metric.unit(), &format!("_p{}", percentile))
+    }
+
+    fn name_with_suffix(&self, name: &str, unit: &str, suffix: &str) -> String {
+        let mut name = name.to_string();
+        if self.unit_suffix && !unit.is_empty() && unit != "1" && !unit.starts_with('{') {
+            name.push('_');
+            name.push_str(unit);
+        }
+        let mut name = self.sanitize(name);
+        let suffix = self.sanitize(suffix.to_string());
+        let max_len = self.max_name_len.saturating_sub(suffix.chars().count());
+        if let Some((index, _)) = name.char_indices().nth(max_len) {
+            name.truncate(index);
+        }
+        name.push_str(&suffix);
+        name
+    }
+
+    pub(crate) fn namespace(&self, scope: &InstrumentationScope) -> Option<String> {
+        match &self.namespace {
+            Namespace::Default => None,
+            Namespace::Scope if scope.name().is_empty() => None,
+            Namespace::Scope => Some(self.sanitize(scope.name().to_string())),
+            Namespace::Fixed(namespace) => Some(self.sanitize(namespace.to_string())),
+        }
+    }
+
+    fn sanitize(&self, value: String) -> String {
+        if !self.sanitization {
+            return value;
+        }
+        let mut sanitized = String::with_capacity(value.len());
+        for c in value.chars() {
+            let c = match c {
+                'µ' | 'μ' => 'u',
+                c if c.is_ascii_alphanumeric() || c == '-' || c == '/' => c,
+                _ => '_',
+            };
+            if !(c == '_' && sanitized.ends_with('_')) {
+                sanitized.push(c);
+            }
+        }
+        while sanitized.ends_with('_') {
+            sanitized.pop();
+        }
+        sanitized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize() {
+        let policy = MetricNamingPolicy::new().with_sanitization(true);
+        assert_eq!(
+            policy.sanitize("http.server.request.duration".into()),
+            "http_server_request_duration"
+        );
+        assert_eq!(policy.sanitize("queue size (µs)".into()), "queue_size_us");
+        assert_eq!(policy.sanitize("a-b/c__d".into()), "a-b/c_d");
+        assert_eq!(
+            MetricNamingPolicy::new().sanitize("a.b c".into()),
+            "a.b c",
+            "disabled by default"
+        );
+    }
+
+    #[test]
+    fn name() {
+        let policy = MetricNamingPolicy::new()
+            .with_unit_suffix(true)
+            .with_sanitization(true);
+        assert_eq!(
+            policy.name_with_suffix("request.duration", "ms", "_p99.9"),
+            "request_duration_ms_p99_9"
+        );
+        assert_eq!(
+            policy.name_with_suffix("queue.latency", "µs", ""),
+            "queue_latency_us"
+        );
+        assert_eq!(
+            policy.name_with_suffix("requests", "{request}", ""),
+            "requests"
+        );
+        let policy = policy.with_max_name_len(10);
+        assert_eq!(
+            policy.name_with_suffix("request.duration", "ms", ""),
+            "request_du"
+        );
+        assert_eq!(
+            policy.name_with_suffix("request.duration", "ms", "_p99.9"),
+            "requ_p99_9",
+            "keeps the percentile suffix"
+        );
+    }
+
+    #[test]
+    fn namespace() {
+        let scope = InstrumentationScope::builder("my.library").build();
+        let unnamed = InstrumentationScope::builder("").build();
+        assert_eq!(MetricNamingPolicy::new().namespace(&scope), None);
+        let policy = MetricNamingPolicy::new().with_scope_namespace();
+        assert_eq!(policy.namespace(&scope), Some("my.library".into()));
+        assert_eq!(policy.namespace(&unnamed), None);
+        let policy = MetricNamingPolicy::new()
+            .with_namespace("team.a")
+            .with_sanitization(true);
+        assert_eq!(policy.namespace(&scope), Some("team_a".into()));
+    }
+}
@@ -1,16 +1,22 @@
 use crate::{
     convert::time_to_string,
-    models::{Data, DataPoint, DataPointType, Envelope, MetricData, Properties},
+    cumulative::{Cumulative, CumulativeToDelta, SeriesKey},
+    models::{
+        context_tag_keys::{self as tags, Tags},
+        Data, DataPoint, DataPointType, Envelope, MetricData, Properties,
+    },
     tags::get_tags_for_metric,
     Exporter,
 };
 use async_trait::async_trait;
-use opentelemetry::KeyValue;
+use opentelemetry::{InstrumentationScope, KeyValue};
 use opentelemetry_http::HttpClient;
 use opentelemetry_sdk::{
     error::OTelSdkResult,
     metrics::{
-        data::{ExponentialHistogram, Gauge, Histogram, Metric, ResourceMetrics, Sum},
+        data::{
+            ExponentialBucket, ExponentialHistogram, Gauge, Histogram, Metric, ResourceMetrics, Sum,
+        },
         exporter::PushMetricExporter,
         Temporality,
     },
@@ -21,6 +27,12 @@ use std::{
     time::{Duration, SystemTime},
 };
 
+/// Name of the instrumentation scope of the standard metrics, see
+/// [`StandardMetricsSpanProcessor`](crate::StandardMetricsSpanProcessor).
+#[cfg(feature = "trace")]
+pub(crate) const STANDARD_METRICS_SCOPE: &str =
+    "opentelemetry-application-insights/standard-metrics";
+
 #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
 #[async_trait]
 impl<C> PushMetricExporter for Exporter<C>
@@ -36,23 +48,52 @@ where
 
         let mut envelopes = Vec::new();
         for scope_metrics in metrics.scope_metrics() {
+            let standard = is_standard_metrics_scope(scope_metrics.scope());
             for metric in scope_metrics.metrics() {
-                let data_points = map_metric(metric);
-                for data in data_points {
+                let (name, namespace, percentiles) = if standard {
+                    (metric.name().to_string(), None, Vec::new())
+                } else {
+                    (
+                        self.metric_naming_policy.name(metric),
+                        self.metric_naming_policy.namespace(scope_metrics.scope()),
+                        self.histogram_percentiles
+                            .iter()
+                            .map(|&percentile| {
+                                let name = self
+                                    .metric_naming_policy
+                                    .percentile_name(metric, percentile);
+                                (percentile, name)
+                            })
+                            .collect(),
+                    )
+                };
+                let data_points = map_metric(&MetricContext {
+                    scope: scope_metrics.scope(),
+                    metric,
+                    name: &name,
+                    percentiles: &percentiles,
+                    deltas: &self.cumulative_to_delta,
+                });
+                for mut data in data_points {
+                    data.data.ns = namespace.clone().map(Into::into);
                     let tags =
                         get_tags_for_metric(metrics.resource(), scope_metrics.scope(), &data.attrs);
-                    let properties: Properties = metrics
-                        .resource()
-                        .iter()
-                        .chain(
-                            scope_metrics
-                                .scope()
-                                .attributes()
-                                .map(|kv| (&kv.key, &kv.value)),
-                        )
-                        .chain(data.attrs.iter().map(|kv| (&kv.key, &kv.value)))
-                        .map(|(k, v)| (k.as_str().into(), v.into()))
-                        .collect();
+                    let properties: Properties = if standard {
+                        standard_metric_properties(metric.name(), &tags, &data.attrs)
+                    } else {
+                        metrics
+                            .resource()
+                            .iter()
+                            .chain(
+                                scope_metrics
+                                    .scope()
+                                    .attributes()
+                                    .map(|kv| (&kv.key, &kv.value)),
+                            )
+                            .chain(data.attrs.iter().map(|kv| (&kv.key, &kv.value)))
+                            .map(|(k, v)| (k.as_str().into(), v.into()))
+                            .collect()
+                    };
                     envelopes.push(Envelope {
                         name: "Microsoft.ApplicationInsights.Metric",
                         time: time_to_string(data.time).into(),
@@ -68,6 +109,7 @@ where
                 }
             }
         }
+        self.cumulative_to_delta.finish_export();
 
         async move {
             crate::uploader::send(
@@ -90,7 +132,8 @@ where
     }
 
     fn temporality(&self) -> Temporality {
-        // Application Insights only supports Delta temporality as defined in the spec:
+        // Application Insights only supports Delta temporality. By default, use it as defined in
+        // the spec:
         //
         // > Choose Delta aggregation temporality for Counter, Asynchronous Counter and Histogram
         // > instrument kinds, choose Cumulative aggregation for UpDownCounter and Asynchronous
@@ -99,8 +142,38 @@ where
         // See:
         // - https://github.com/open-telemetry/opentelemetry-specification/blob/58bfe48eabe887545198d66c43f44071b822373f/specification/metrics/sdk_exporters/otlp.md?plain=1#L46-L47
         // - https://github.com/frigus02/opentelemetry-application-insights/issues/74#issuecomment-2108488385
-        Temporality::Delta
+        //
+        // Cumulative sums and histograms are converted to Delta before they're exported.
+        self.temporality
+    }
+}
+
+#[cfg(feature = "trace")]
+fn is_standard_metrics_scope(scope: &InstrumentationScope) -> bool {
+    scope.name() == STANDARD_METRICS_SCOPE
+}
+
+#[cfg(not(feature = "trace"))]
+fn is_standard_metrics_scope(_scope: &InstrumentationScope) -> bool {
+    false
+}
+
+/// Properties of a standard metric: its dimensions, the metric id and the cloud role, which
+/// Application Insights uses to match the metric with the request and dependency telemetry.
+fn standard_metric_properties(metric_id: &str, tags: &Tags, attrs: &[KeyValue]) -> Properties {
+    let mut properties: Properties = attrs
+        .iter()
+        .map(|kv| (kv.key.as_str().into(), (&kv.value).into()))
+        .collect();
+    properties.insert("_MS.MetricId".into(), metric_id.into());
+    properties.insert("_MS.IsAutocollected".into(), "True".into());
+    if let Some(role) = tags.get(&tags::CLOUD_ROLE) {
+        properties.insert("cloud/roleName".into(), role.as_str().into());
+    }
+    if let Some(role_instance) = tags.get(&tags::CLOUD_ROLE_INSTANCE) {
+        properties.insert("cloud/roleInstance".into(), role_instance.as_str().into());
     }
+    properties
 }
 
 struct EnvelopeData {
@@ -131,32 +204,59 @@ impl ToF64Lossy for f64 {
     }
 }
 
-fn map_metric(metric: &Metric) -> Vec<EnvelopeData> {
+/// Everything needed to map the data points of a metric.
+struct MetricContext<'a> {
+    scope: &'a InstrumentationScope,
+    metric: &'a Metric,
+    name: &'a str,
+    /// Percentiles to estimate for histograms, with the names of their data points.
+    percentiles: &'a [(f64, String)],
+    deltas: &'a CumulativeToDelta,
+}
+
+impl MetricContext<'_> {
+    /// Delta of a cumulative value, or the value if it's a delta already.
+    fn to_delta<'a, T: Cumulative>(
+        &self,
+        temporality: Temporality,
+        start_time: SystemTime,
+        attrs: impl Iterator<Item = &'a KeyValue>,
+        value: T,
+    ) -> T {
+        if temporality != Temporality::Cumulative {
+            return value;
+        }
+        let key = SeriesKey::new(self.scope, self.metric.name(), attrs);
+        self.deltas.delta(key, start_time, value)
+    }
+}
+
+fn map_metric(cx: &MetricContext<'_>) -> Vec<EnvelopeData> {
     use opentelemetry_sdk::metrics::data::{AggregatedMetrics::*, MetricData};
-    match metric.data() {
-        F64(MetricData::Gauge(data)) => map_gauge(metric, data),
-        U64(MetricData::Gauge(data)) => map_gauge(metric, data),
-        I64(MetricData::Gauge(data)) => map_gauge(metric, data),
-        F64(MetricData::Sum(data)) => map_sum(metric, data),
-        U64(MetricData::Sum(data)) => map_sum(metric, data),
-        I64(MetricData::Sum(data)) => map_sum(metric, data),
-        F64(MetricData::Histogram(data)) => map_histogram(metric, data),
-        U64(MetricData::Histogram(data)) => map_histogram(metric, data),
-        I64(MetricData::Histogram(data)) => map_histogram(metric, data),
-        F64(MetricData::ExponentialHistogram(data)) => map_exponential_histogram(metric, data),
-        U64(MetricData::ExponentialHistogram(data)) => map_exponential_histogram(metric, data),
-        I64(MetricData::ExponentialHistogram(data)) => map_exponential_histogram(metric, data),
-    }
-}
-
-fn map_gauge<T: Copy + ToF64Lossy>(metric: &Metric, gauge: &Gauge<T>) -> Vec<EnvelopeData> {
+    match cx.metric.data() {
+        F64(MetricData::Gauge(data)) => map_gauge(cx, data),
+        U64(MetricData::Gauge(data)) => map_gauge(cx, data),
+        I64(MetricData::Gauge(data)) => map_gauge(cx, data),
+        F64(MetricData::Sum(data)) => map_sum(cx, data),
+        U64(MetricData::Sum(data)) => map_sum(cx, data),
+        I64(MetricData::Sum(data)) => map_sum(cx, data),
+        F64(MetricData::Histogram(data)) => map_histogram(cx, data),
+        U64(MetricData::Histogram(data)) => map_histogram(cx, data),
+        I64(MetricData::Histogram(data)) => map_histogram(cx, data),
+        F64(MetricData::ExponentialHistogram(data)) => map_exponential_histogram(cx, data),
+        U64(MetricData::ExponentialHistogram(data)) => map_exponential_histogram(cx, data),
+        I64(MetricData::ExponentialHistogram(data)) => map_exponential_histogram(cx, data),
+    }
+}
+
+fn map_gauge<T: Copy + ToF64Lossy>(cx: &MetricContext<'_>, gauge: &Gauge<T>) -> Vec<EnvelopeData> {
     gauge
         .data_points()
         .map(|data_point| {
             let time = gauge.time();
             let data = DataPoint {
                 ns: None,
-                name: metric.name().into(),
+                name: cx.name.into(),
                 kind: Some(DataPointType::Measurement),
                 value: data_point.value().to_f64_lossy(),
             };
@@ -166,73 +266,390 @@ fn map_gauge<T: Copy + ToF64Lossy>(metric: &Metric, gauge: &Gauge<T>) -> Vec<Env
         .collect()
 }
 
+/// Counts of an explicit bucket histogram data point.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct HistogramCounts {
+    pub(crate) count: u64,
+    pub(crate) sum: f64,
+    pub(crate) min: Option<f64>,
+    pub(crate) max: Option<f64>,
+    pub(crate) bucket_counts: Vec<u64>,
+}
+
+/// Counts of an exponential histogram data point.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ExponentialHistogramCounts {
+    pub(crate) count: u64,
+    pub(crate) sum: f64,
+    pub(crate) min: Option<f64>,
+    pub(crate) max: Option<f64>,
+    pub(crate) scale: i8,
+    pub(crate) zero_count: u64,
+    pub(crate) positive: ExponentialBuckets,
+    pub(crate) negative: ExponentialBuckets,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct ExponentialBuckets {
+    pub(crate) offset: i32,
+    pub(crate) counts: Vec<u64>,
+}
+
+impl From<&ExponentialBucket> for ExponentialBuckets {
+    fn from(bucket: &ExponentialBucket) -> Self {
+        Self {
+            offset: bucket.offset(),
+            counts: bucket.counts().collect(),
+        }
+    }
+}
+
 fn map_histogram<T: Copy + ToF64Lossy>(
-    metric: &Metric,
+    cx: &MetricContext<'_>,
     histogram: &Histogram<T>,
 ) -> Vec<EnvelopeData> {
     histogram
         .data_points()
-        .map(|data_point| {
-            let time = histogram.time();
-            let data = DataPoint {
-                ns: None,
-                name: metric.name().into(),
-                kind: Some(DataPointType::Aggregation {
-                    count: Some(data_point.count().try_into().unwrap_or_default()),
+        .flat_map(|data_point| {
+            let counts = cx.to_delta(
+                histogram.temporality(),
+                histogram.start_time(),
+                data_point.attributes(),
+                HistogramCounts {
+                    count: data_point.count(),
+                    sum: data_point.sum().to_f64_lossy(),
                     min: data_point.min().as_ref().map(ToF64Lossy::to_f64_lossy),
                     max: data_point.max().as_ref().map(ToF64Lossy::to_f64_lossy),
-                    std_dev: None,
-                }),
-                value: data_point.sum().to_f64_lossy(),
-            };
-            let attrs = data_point.attributes().cloned().collect();
-            EnvelopeData { time, data, attrs }
+                    bucket_counts: data_point.bucket_counts().collect(),
+                },
+            );
+            let buckets = explicit_buckets(
+                &data_point.bounds().collect::<Vec<_>>(),
+                counts.bucket_counts.iter().copied(),
+                counts.min,
+                counts.max,
+            );
+            map_histogram_data_point(
+                cx,
+                histogram.time(),
+                HistogramSummary {
+                    count: counts.count,
+                    sum: counts.sum,
+                    min: counts.min,
+                    max: counts.max,
+                    buckets,
+                },
+                data_point.attributes(),
+            )
         })
         .collect()
 }
 
 fn map_exponential_histogram<T: Copy + ToF64Lossy>(
-    metric: &Metric,
+    cx: &MetricContext<'_>,
     exp_histogram: &ExponentialHistogram<T>,
 ) -> Vec<EnvelopeData> {
     exp_histogram
         .data_points()
-        .map(|data_point| {
-            let time = exp_histogram.time();
-            let data = DataPoint {
-                ns: None,
-                name: metric.name().into(),
-                kind: Some(DataPointType::Aggregation {
-                    count: Some(data_point.count().try_into().unwrap_or_default()),
+        .flat_map(|data_point| {
+            let counts = cx.to_delta(
+                exp_histogram.temporality(),
+                exp_histogram.start_time(),
+                data_point.attributes(),
+                ExponentialHistogramCounts {
+                    count: data_point.count() as u64,
+                    sum: data_point.sum().to_f64_lossy(),
                     min: data_point.min().as_ref().map(ToF64Lossy::to_f64_lossy),
                     max: data_point.max().as_ref().map(ToF64Lossy::to_f64_lossy),
-                    std_dev: None,
-                }),
-                value: data_point.sum().to_f64_lossy(),
-            };
-            let attrs = data_point.attributes().cloned().collect();
-            EnvelopeData { time, data, attrs }
+                    scale: data_point.scale(),
+                    zero_count: data_point.zero_count(),
+                    positive: data_point.positive_bucket().into(),
+                    negative: data_point.negative_bucket().into(),
+                },
+            );
+            let buckets = exponential_buckets(&counts, data_point.zero_threshold());
+            map_histogram_data_point(
+                cx,
+                exp_histogram.time(),
+                HistogramSummary {
+                    count: counts.count,
+                    sum: counts.sum,
+                    min: counts.min,
+                    max: counts.max,
+                    buckets,
+                },
+                data_point.attributes(),
+            )
+        })
+        .collect()
+}
+
+struct HistogramSummary {
+    count: u64,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+    buckets: Vec<Bucket>,
+}
+
+/// Aggregation of a histogram data point, followed by a measurement for each of the estimated
+/// percentiles, named `<name>_p<percentile>`.
+fn map_histogram_data_point<'a>(
+    cx: &MetricContext<'_>,
+    time: SystemTime,
+    summary: HistogramSummary,
+    attrs: impl Iterator<Item = &'a KeyValue>,
+) -> Vec<EnvelopeData> {
+    let attrs: Vec<KeyValue> = attrs.cloned().collect();
+    let mut result = vec![EnvelopeData {
+        time,
+        data: DataPoint {
+            ns: None,
+            name: cx.name.into(),
+            kind: Some(DataPointType::Aggregation {
+                count: Some(summary.count.try_into().unwrap_or_default()),
+                min: summary.min,
+                max: summary.max,
+                std_dev: estimate_std_dev(&summary.buckets, summary.count, summary.sum),
+            }),
+            value: summary.sum,
+        },
+        attrs: attrs.clone(),
+    }];
+    for (percentile, name) in cx.percentiles {
+        if let Some(value) = estimate_percentile(&summary.buckets, summary.count, *percentile) {
+            result.push(EnvelopeData {
+                time,
+                data: DataPoint {
+                    ns: None,
+                    name: name.clone().into(),
+                    kind: Some(DataPointType::Measurement),
+                    value,
+                },
+                attrs: attrs.clone(),
+            });
+        }
+    }
+    result
+}
+
+/// Histogram bucket with finite bounds.
+#[derive(Debug, PartialEq)]
+struct Bucket {
+    lower: f64,
+    upper: f64,
+    count: u64,
+}
+
+/// Buckets of an explicit bucket histogram. The open-ended first and last buckets are bounded by
+/// the min and max values. Without any bounds, there's only a bucket from min to max.
+fn explicit_buckets(
+    bounds: &[f64],
+    counts: impl Iterator<Item = u64>,
+    min: Option<f64>,
+    max: Option<f64>,
+) -> Vec<Bucket> {
+    let buckets = counts.enumerate().filter_map(|(i, count)| {
+        let lower = match i {
+            0 => min.or_else(|| bounds.first().copied())?,
+            _ => *bounds.get(i - 1)?,
+        };
+        let upper = bounds
+            .get(i)
+            .copied()
+            .or(max)
+            .or_else(|| bounds.last().copied())?;
+        Some(Bucket {
+            lower,
+            upper,
+            count,
+        })
+    });
+    clamp_buckets(buckets, min, max)
+}
+
+/// Buckets of an exponential histogram in ascending order: negative buckets, zero bucket and
+/// positive buckets.
+fn exponential_buckets(counts: &ExponentialHistogramCounts, zero_threshold: f64) -> Vec<Bucket> {
+    let base = 2f64.powf(2f64.powi(-i32::from(counts.scale)));
+    let bounds = |buckets: &ExponentialBuckets| {
+        buckets
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| {
+                let index = buckets.offset + i as i32;
+                (base.powi(index), base.powi(index + 1), count)
+            })
+            .collect::<Vec<_>>()
+    };
+    let negative = bounds(&counts.negative)
+        .into_iter()
+        .rev()
+        .map(|(lower, upper, count)| Bucket {
+            lower: -upper,
+            upper: -lower,
+            count,
+        });
+    let zero = Bucket {
+        lower: -zero_threshold,
+        upper: zero_threshold,
+        count: counts.zero_count,
+    };
+    let positive = bounds(&counts.positive)
+        .into_iter()
+        .map(|(lower, upper, count)| Bucket {
+            lower,
+            upper,
+            count,
+        });
+    clamp_buckets(
+        negative.chain(std::iter::once(zero)).chain(positive),
+        counts.min,
+        counts.max,
+    )
+}
+
+/// Remove empty buckets and limit the bounds to the min and max values.
+fn clamp_buckets(
+    buckets: impl Iterator<Item = Bucket>,
+    min: Option<f64>,
+    max: Option<f64>,
+) -> Vec<Bucket> {
+    buckets
+        .filter(|bucket| bucket.count > 0)
+        .map(|bucket| {
+            let lower = min.map_or(bucket.lower, |min| bucket.lower.max(min));
+            let upper = max.map_or(bucket.upper, |max| bucket.upper.min(max));
+            Bucket {
+                lower: lower.min(upper),
+                upper,
+                count: bucket.count,
+            }
         })
         .collect()
 }
 
-fn map_sum<T: Copy + ToF64Lossy>(metric: &Metric, sum: &Sum<T>) -> Vec<EnvelopeData> {
+/// Estimated standard deviation, assuming all values of a bucket are in its middle.
+fn estimate_std_dev(buckets: &[Bucket], count: u64, sum: f64) -> Option<f64> {
+    if buckets.is_empty() || count == 0 {
+        return None;
+    }
+    let mean = sum / count as f64;
+    let variance = buckets
+        .iter()
+        .map(|bucket| {
+            let middle = (bucket.lower + bucket.upper) / 2.0;
+            bucket.count as f64 * (middle - mean).powi(2)
+        })
+        .sum::<f64>()
+        / count as f64;
+    Some(variance.sqrt())
+}
+
+/// Estimated percentile (0-100), assuming the values of a bucket are evenly distributed.
+fn estimate_percentile(buckets: &[Bucket], count: u64, percentile: f64) -> Option<f64> {
+    if buckets.is_empty() || count == 0 || !(0.0..=100.0).contains(&percentile) {
+        return None;
+    }
+    let rank = percentile / 100.0 * count as f64;
+    let mut cumulative = 0.0;
+    for bucket in buckets {
+        let bucket_count = bucket.count as f64;
+        if cumulative + bucket_count >= rank {
+            let fraction = (rank - cumulative) / bucket_count;
+            return Some(bucket.lower + (bucket.upper - bucket.lower) * fraction);
+        }
+        cumulative += bucket_count;
+    }
+    buckets.last().map(|bucket| bucket.upper)
+}
+
+fn map_sum<T: Copy + ToF64Lossy>(cx: &MetricContext<'_>, sum: &Sum<T>) -> Vec<EnvelopeData> {
     sum.data_points()
         .map(|data_point| {
             let time = sum.time();
+            let mut value = data_point.value().to_f64_lossy();
+            // Non-monotonic sums like up-down counters stay cumulative.
+            if sum.is_monotonic() {
+                value = cx.to_delta(
+                    sum.temporality(),
+                    sum.start_time(),
+                    data_point.attributes(),
+                    value,
+                );
+            }
             let data = DataPoint {
                 ns: None,
-                name: metric.name().into(),
+                name: cx.name.into(),
                 kind: Some(DataPointType::Aggregation {
                     count: None,
                     min: None,
                     max: None,
                     std_dev: None,
                 }),
-                value: data_point.value().to_f64_lossy(),
+                value,
             };
             let attrs = data_point.attributes().cloned().collect();
             EnvelopeData { time, data, attrs }
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket(lower: f64, upper: f64, count: u64) -> Bucket {
+        Bucket {
+            lower,
+            upper,
+            count,
+        }
+    }
+
+    #[test]
+    fn explicit_buckets_bounded_by_min_and_max() {
+        let buckets = explicit_buckets(
+            &[10.0, 20.0, 50.0],
+            vec![1, 0, 2, 1].into_iter(),
+            Some(5.0),
+            Some(70.0),
+        );
+        assert_eq!(
+            buckets,
+            vec![
+                bucket(5.0, 10.0, 1),
+                bucket(20.0, 50.0, 2),
+                bucket(50.0, 70.0, 1),
+            ]
+        );
+
+        let buckets = explicit_buckets(&[], vec![3].into_iter(), Some(1.0), Some(4.0));
+        assert_eq!(buckets, vec![bucket(1.0, 4.0, 3)]);
+        assert!(explicit_buckets(&[], vec![3].into_iter(), None, None).is_empty());
+    }
+
+    #[test]
+    fn percentiles() {
+        let buckets = vec![
+            bucket(0.0, 10.0, 50),
+            bucket(10.0, 20.0, 40),
+            bucket(20.0, 100.0, 10),
+        ];
+        assert_eq!(estimate_percentile(&buckets, 100, 0.0), Some(0.0));
+        assert_eq!(estimate_percentile(&buckets, 100, 50.0), Some(10.0));
+        assert_eq!(estimate_percentile(&buckets, 100, 75.0), Some(16.25));
+        assert_eq!(estimate_percentile(&buckets, 100, 95.0), Some(60.0));
+        assert_eq!(estimate_percentile(&buckets, 100, 100.0), Some(100.0));
+        assert_eq!(estimate_percentile(&buckets, 100, 101.0), None);
+        assert_eq!(estimate_percentile(&[], 0, 50.0), None);
+    }
+
+    #[test]
+    fn std_dev() {
+        let buckets = vec![bucket(0.0, 2.0, 2), bucket(2.0, 4.0, 2)];
+        assert_eq!(estimate_std_dev(&buckets, 4, 8.0), Some(1.0));
+        assert_eq!(estimate_std_dev(&[], 0, 0.0), None);
+    }
+}
@@ -14,7 +14,7 @@ impl ContextTagKey {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub(crate) struct Tags(BTreeMap<&'static str, String>);
 
 impl Tags {
@@ -32,7 +32,12 @@ impl Tags {
         self.0.remove(key.key)
     }
 
-    #[cfg(test)]
+    #[cfg(feature = "trace")]
+    pub(crate) fn contains_key(&self, key: &ContextTagKey) -> bool {
+        self.0.contains_key(key.key)
+    }
+
+    #[cfg(any(test, feature = "trace", feature = "logs", feature = "metrics"))]
     pub(crate) fn get(&self, key: &ContextTagKey) -> Option<&String> {
         self.0.get(key.key)
     }
@@ -79,6 +84,19 @@ macro_rules! context_tag_keys {
             ///
             /// If not specified, the custom event name defaults to "&lt;no name&gt;".
             pub const CUSTOM_EVENT_NAME: &str = "ai.customEvent.name";
+
+            /// Marks a span as a page view, if set to `true`.
+            ///
+            /// Page views are used by the usage features of Application Insights, e.g. for web
+            /// front-ends built with WebAssembly. The span name becomes the page name.
+            pub const PAGE_VIEW: &str = "ai.pageView";
+
+            /// Messages of the sources of an exception (see [`std::error::Error::source`]) as a
+            /// string array, beginning with the direct source.
+            ///
+            /// Applies to exception events and exception logs. Each source is sent as a nested
+            /// exception. See also `SpanExt::record_error_chain`, which sets this attribute.
+            pub const EXCEPTION_SOURCE_MESSAGES: &str = "ai.exception.source.messages";
         }
 
         $($(#[doc = $doc])+
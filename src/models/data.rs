@@ -1,16 +1,18 @@
 #[cfg(feature = "metrics")]
 use crate::models::MetricData;
-#[cfg(feature = "trace")]
-use crate::models::{EventData, RemoteDependencyData, RequestData};
 #[cfg(any(feature = "trace", feature = "logs"))]
-use crate::models::{ExceptionData, MessageData};
+use crate::models::{EventData, ExceptionData, MessageData};
+#[cfg(any(feature = "trace", feature = "logs"))]
+use crate::models::{Measurements, Properties};
+#[cfg(feature = "trace")]
+use crate::models::{PageViewData, RemoteDependencyData, RequestData};
 use serde::Serialize;
 
 /// Data struct to contain both B and C sections.
 #[derive(Debug, Serialize)]
 #[serde(tag = "baseType", content = "baseData")]
 pub(crate) enum Data {
-    #[cfg(feature = "trace")]
+    #[cfg(any(feature = "trace", feature = "logs"))]
     #[serde(rename = "EventData")]
     Event(EventData),
     #[cfg(any(feature = "trace", feature = "logs"))]
@@ -23,9 +25,32 @@ pub(crate) enum Data {
     #[serde(rename = "MetricData")]
     Metric(MetricData),
     #[cfg(feature = "trace")]
+    #[serde(rename = "PageViewData")]
+    PageView(PageViewData),
+    #[cfg(feature = "trace")]
     #[serde(rename = "RemoteDependencyData")]
     RemoteDependency(RemoteDependencyData),
     #[cfg(feature = "trace")]
     #[serde(rename = "RequestData")]
     Request(RequestData),
 }
+
+impl Data {
+    /// Properties and measurements of data types, which support measurements.
+    #[cfg(any(feature = "trace", feature = "logs"))]
+    pub(crate) fn properties_and_measurements_mut(
+        &mut self,
+    ) -> Option<(&mut Option<Properties>, &mut Option<Measurements>)> {
+        match self {
+            Data::Event(data) => Some((&mut data.properties, &mut data.measurements)),
+            Data::Exception(data) => Some((&mut data.properties, &mut data.measurements)),
+            #[cfg(feature = "trace")]
+            Data::PageView(data) => Some((&mut data.properties, &mut data.measurements)),
+            #[cfg(feature = "trace")]
+            Data::RemoteDependency(data) => Some((&mut data.properties, &mut data.measurements)),
+            #[cfg(feature = "trace")]
+            Data::Request(data) => Some((&mut data.properties, &mut data.measurements)),
+            _ => None,
+        }
+    }
+}
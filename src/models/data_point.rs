@@ -39,7 +39,7 @@ pub(crate) enum DataPointType {
         max: Option<f64>,
 
         /// Standard deviation of the aggregated metric. Should not be set for a measurement.
-        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(rename = "stdDev", skip_serializing_if = "Option::is_none")]
         std_dev: Option<f64>,
     },
 }
@@ -1,4 +1,4 @@
-use crate::models::{LimitedLenString, Properties};
+use crate::models::{LimitedLenString, Measurements, Properties};
 use serde::Serialize;
 
 /// Instances of Event represent structured event records that can be grouped and searched by their
@@ -15,4 +15,8 @@ pub(crate) struct EventData {
     /// Collection of custom properties.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) properties: Option<Properties>,
+
+    /// Collection of custom measurements.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) measurements: Option<Measurements>,
 }
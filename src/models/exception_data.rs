@@ -1,9 +1,9 @@
-use crate::models::{ExceptionDetails, Properties, SeverityLevel};
+use crate::models::{ExceptionDetails, Measurements, Properties, SeverityLevel};
 use serde::Serialize;
 
 /// An instance of Exception represents a handled or unhandled exception that occurred during
 /// execution of the monitored application.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct ExceptionData {
     /// Schema version
@@ -20,4 +20,8 @@ pub(crate) struct ExceptionData {
     /// Collection of custom properties.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) properties: Option<Properties>,
+
+    /// Collection of custom measurements.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) measurements: Option<Measurements>,
 }
@@ -4,7 +4,7 @@ use serde::Serialize;
 /// Instances of Message represent printf-like trace statements that are text-searched. Log4Net,
 /// NLog and other text-based log file entries are translated into intances of this type. The
 /// message does not have measurements.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct MessageData {
     /// Schema version
@@ -3,7 +3,7 @@ mod data;
 #[cfg(feature = "metrics")]
 mod data_point;
 mod envelope;
-#[cfg(feature = "trace")]
+#[cfg(any(feature = "trace", feature = "logs"))]
 mod event_data;
 #[cfg(any(feature = "trace", feature = "logs"))]
 mod exception_data;
@@ -15,6 +15,8 @@ mod message_data;
 mod metric_data;
 #[cfg(feature = "trace")]
 mod ms_link;
+#[cfg(feature = "trace")]
+mod page_view_data;
 #[cfg(feature = "live-metrics")]
 mod quick_pulse;
 #[cfg(feature = "trace")]
@@ -24,12 +26,14 @@ mod request_data;
 mod sanitize;
 #[cfg(any(feature = "trace", feature = "logs"))]
 mod severity_level;
+#[cfg(any(feature = "trace", feature = "logs"))]
+mod stack_frame;
 
 pub(crate) use data::*;
 #[cfg(feature = "metrics")]
 pub(crate) use data_point::*;
 pub(crate) use envelope::*;
-#[cfg(feature = "trace")]
+#[cfg(any(feature = "trace", feature = "logs"))]
 pub(crate) use event_data::*;
 #[cfg(any(feature = "trace", feature = "logs"))]
 pub(crate) use exception_data::*;
@@ -41,6 +45,8 @@ pub(crate) use message_data::*;
 pub(crate) use metric_data::*;
 #[cfg(feature = "trace")]
 pub(crate) use ms_link::*;
+#[cfg(feature = "trace")]
+pub(crate) use page_view_data::*;
 #[cfg(feature = "live-metrics")]
 pub(crate) use quick_pulse::*;
 #[cfg(feature = "trace")]
@@ -50,6 +56,8 @@ pub(crate) use request_data::*;
 pub(crate) use sanitize::*;
 #[cfg(any(feature = "trace", feature = "logs"))]
 pub(crate) use severity_level::*;
+#[cfg(any(feature = "trace", feature = "logs"))]
+pub(crate) use stack_frame::*;
 
 #[cfg(test)]
 mod tests {
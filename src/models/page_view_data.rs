@@ -0,0 +1,42 @@
+use crate::models::{LimitedLenString, Measurements, Properties};
+use serde::Serialize;
+
+/// An instance of PageView represents a view of a page or screen of an application by a user, e.g.
+/// the navigation to a page in a single-page application.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PageViewData {
+    /// Schema version
+    pub(crate) ver: i32,
+
+    /// Name of the page, e.g. its title or route. Keep it low cardinality to allow proper grouping
+    /// and useful metrics.
+    pub(crate) name: LimitedLenString<512>,
+
+    /// URL of the page with all query string parameters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) url: Option<LimitedLenString<2048>>,
+
+    /// Page view duration in format: DD.HH:MM:SS.MMMMMM. For a page view (PageViewData), this is the
+    /// duration. For a page view with performance information (PageViewPerfData), this is the
+    /// page load time. Must be less than 1000 days.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) duration: Option<String>,
+
+    /// Fully qualified page URI or URL of the referring page; if unknown, leave blank
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) referrer_uri: Option<LimitedLenString<2048>>,
+
+    /// Identifier of a page view instance. Used for correlation between page view and other
+    /// telemetry items.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) id: Option<LimitedLenString<128>>,
+
+    /// Collection of custom properties.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) properties: Option<Properties>,
+
+    /// Collection of custom measurements.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) measurements: Option<Measurements>,
+}
@@ -1,4 +1,4 @@
-use crate::models::{LimitedLenString, Properties};
+use crate::models::{LimitedLenString, Measurements, Properties};
 use serde::Serialize;
 
 /// An instance of Remote Dependency represents an interaction of the monitored component with a
@@ -47,4 +47,8 @@ pub(crate) struct RemoteDependencyData {
     /// Collection of custom properties.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) properties: Option<Properties>,
+
+    /// Collection of custom measurements.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) measurements: Option<Measurements>,
 }
@@ -2,7 +2,7 @@ use crate::convert::AttrValue;
 use serde::Serialize;
 use std::{borrow::Cow, collections::BTreeMap};
 
-#[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Serialize)]
 pub(crate) struct LimitedLenString<const N: usize>(String);
 
 impl<const N: usize> From<&str> for LimitedLenString<N> {
@@ -53,3 +53,6 @@ impl<const N: usize> AsRef<str> for LimitedLenString<N> {
 }
 
 pub(crate) type Properties = BTreeMap<LimitedLenString<150>, LimitedLenString<8192>>;
+
+#[cfg(any(feature = "trace", feature = "logs"))]
+pub(crate) type Measurements = BTreeMap<LimitedLenString<150>, f64>;
@@ -0,0 +1,256 @@
+use crate::{
+    convert::attrs_to_map,
+    models::context_tag_keys::{self as tags, Tags},
+    synthetic::SharedSyntheticSourceDetector,
+    tags::get_http_operation_name,
+    Exporter,
+};
+use opentelemetry::{
+    trace::{SpanId, SpanKind, TraceContextExt as _, TraceId},
+    Context,
+};
+use opentelemetry_sdk::{
+    error::OTelSdkResult,
+    trace::{Span, SpanData, SpanProcessor},
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Maximum number of traces for which the operation context is remembered.
+const MAX_TRACES: usize = 10_000;
+
+/// Context of the root request of a trace, which applies to all telemetry of the trace.
+#[derive(Clone, Debug)]
+pub(crate) struct OperationContext {
+    root_span_id: SpanId,
+    pub(crate) name: Option<String>,
+    pub(crate) synthetic_source: Option<String>,
+}
+
+/// Bounded cache of operation contexts by trace id. When full, the oldest traces are dropped.
+#[derive(Debug, Default)]
+pub(crate) struct OperationContexts {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    contexts: HashMap<TraceId, OperationContext>,
+    order: VecDeque<TraceId>,
+}
+
+impl OperationContexts {
+    pub(crate) fn get(&self, trace_id: TraceId) -> Option<OperationContext> {
+        let inner = self.inner.lock().unwrap();
+        inner.contexts.get(&trace_id).cloned()
+    }
+
+    fn insert(&self, trace_id: TraceId, context: OperationContext) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.contexts.insert(trace_id, context).is_none() {
+            inner.order.push_back(trace_id);
+            while inner.order.len() > MAX_TRACES {
+                if let Some(oldest) = inner.order.pop_front() {
+                    inner.contexts.remove(&oldest);
+                }
+            }
+        }
+    }
+
+    fn update(&self, trace_id: TraceId, span_id: SpanId, f: impl FnOnce(&mut OperationContext)) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(context) = inner.contexts.get_mut(&trace_id) {
+            if context.root_span_id == span_id {
+                f(context);
+            }
+        }
+    }
+
+    /// Add the operation name and synthetic source of the trace to the tags, unless they already
+    /// have them.
+    pub(crate) fn apply_to_tags(&self, trace_id: TraceId, tags: &mut Tags) {
+        let context = match self.get(trace_id) {
+            Some(context) => context,
+            None => return,
+        };
+        if let Some(name) = context.name {
+            if !tags.contains_key(&tags::OPERATION_NAME) {
+                tags.insert(tags::OPERATION_NAME, name);
+            }
+        }
+        if let Some(synthetic_source) = context.synthetic_source {
+            if !tags.contains_key(&tags::OPERATION_SYNTHETIC_SOURCE) {
+                tags.insert(tags::OPERATION_SYNTHETIC_SOURCE, synthetic_source);
+            }
+        }
+    }
+}
+
+/// Span processor, which remembers the operation name of the root request of each trace.
+///
+/// The exporter uses this to set the operation name (`ai.operation.name`) on all telemetry of the
+/// trace, including dependencies, span events and logs. This makes the operation views and the
+/// failure drill-downs in Application Insights work. If the exporter has a
+/// [`SyntheticSourceDetector`](crate::SyntheticSourceDetector), the synthetic source
+/// (`ai.operation.syntheticSource`) of the root request is set on all telemetry of the trace as
+/// well.
+///
+/// The operation name is `METHOD /the/route/path` if the root request has the attributes
+/// `http.request.method` and `http.route`, otherwise the span name. The processor remembers up
+/// to 10,000 traces and drops the oldest ones first.
+///
+/// ```
+/// use opentelemetry_sdk::trace::SdkTracerProvider;
+///
+/// # let http_client = reqwest::blocking::Client::new();
+/// let exporter = opentelemetry_application_insights::Exporter::new_from_connection_string(
+///     "InstrumentationKey=00000000-0000-0000-0000-000000000000",
+///     http_client,
+/// )
+/// .expect("valid connection string");
+/// let tracer_provider = SdkTracerProvider::builder()
+///     .with_span_processor(
+///         opentelemetry_application_insights::OperationContextSpanProcessor::new(&exporter),
+///     )
+///     .with_batch_exporter(exporter)
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct OperationContextSpanProcessor {
+    contexts: Arc<OperationContexts>,
+    synthetic_source_detector: SharedSyntheticSourceDetector,
+}
+
+impl OperationContextSpanProcessor {
+    /// Create a new operation context span processor, which works with the given exporter and all
+    /// its clones.
+    pub fn new<C>(exporter: &Exporter<C>) -> Self {
+        Self {
+            contexts: exporter.operation_contexts.clone(),
+            synthetic_source_detector: exporter.synthetic_source_detector.clone(),
+        }
+    }
+
+    fn get_synthetic_source(&self, span: &SpanData) -> Option<String> {
+        self.synthetic_source_detector.detect(span)
+    }
+}
+
+fn get_operation_name(span: &SpanData) -> String {
+    get_http_operation_name(&attrs_to_map(span.attributes.iter()))
+        .unwrap_or_else(|| span.name.to_string())
+}
+
+impl SpanProcessor for OperationContextSpanProcessor {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        let parent_span_context = cx.span().span_context().clone();
+        if parent_span_context.is_valid() && !parent_span_context.is_remote() {
+            return;
+        }
+        let data = match span.exported_data() {
+            Some(data) => data,
+            None => return,
+        };
+        if !matches!(data.span_kind, SpanKind::Server | SpanKind::Consumer) {
+            return;
+        }
+        self.contexts.insert(
+            data.span_context.trace_id(),
+            OperationContext {
+                root_span_id: data.span_context.span_id(),
+                name: Some(get_operation_name(&data)),
+                synthetic_source: self.get_synthetic_source(&data),
+            },
+        );
+    }
+
+    fn on_end(&self, span: SpanData) {
+        // Attributes like `http.route` are often only known after the span started.
+        self.contexts.update(
+            span.span_context.trace_id(),
+            span.span_context.span_id(),
+            |context| {
+                context.name = Some(get_operation_name(&span));
+                context.synthetic_source = self.get_synthetic_source(&span);
+            },
+        );
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn shutdown_with_timeout(&self, _timeout: Duration) -> OTelSdkResult {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_oldest_traces() {
+        let contexts = OperationContexts::default();
+        for i in 0..=MAX_TRACES as u128 {
+            contexts.insert(
+                TraceId::from(i + 1),
+                OperationContext {
+                    root_span_id: SpanId::from(1),
+                    name: Some(i.to_string()),
+                    synthetic_source: None,
+                },
+            );
+        }
+        assert!(contexts.get(TraceId::from(1)).is_none());
+        assert_eq!(
+            contexts.get(TraceId::from(2)).and_then(|x| x.name),
+            Some("1".into())
+        );
+    }
+
+    #[test]
+    fn update_only_for_root_span() {
+        let contexts = OperationContexts::default();
+        let trace_id = TraceId::from(1);
+        contexts.insert(
+            trace_id,
+            OperationContext {
+                root_span_id: SpanId::from(1),
+                name: Some("GET".into()),
+                synthetic_source: None,
+            },
+        );
+        contexts.update(trace_id, SpanId::from(2), |x| x.name = Some("child".into()));
+        contexts.update(trace_id, SpanId::from(1), |x| {
+            x.name = Some("GET /users/{id}".into())
+        });
+        assert_eq!(
+            contexts.get(trace_id).and_then(|x| x.name),
+            Some("GET /users/{id}".into())
+        );
+    }
+
+    #[test]
+    fn detector_set_after_processor_created() {
+        let exporter =
+            Exporter::new_from_connection_string("InstrumentationKey=0fdcec70", ()).unwrap();
+        let processor = OperationContextSpanProcessor::new(&exporter);
+        let _exporter =
+            exporter.with_synthetic_source_detector(crate::SyntheticSourceDetector::new());
+        let span = crate::trace::test_span(
+            SpanKind::Server,
+            vec![opentelemetry::KeyValue::new(
+                "user_agent.original",
+                "kube-probe/1.30",
+            )],
+        );
+        assert_eq!(
+            processor.get_synthetic_source(&span),
+            Some("Kubernetes Probe".into())
+        );
+    }
+}
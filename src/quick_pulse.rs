@@ -1,5 +1,8 @@
 use crate::{
     models::{context_tag_keys, QuickPulseEnvelope, QuickPulseMetric},
+    operation_context::OperationContexts,
+    success::SharedSuccessPolicy,
+    synthetic::SharedSyntheticSourceDetector,
     tags::get_tags_for_resource,
     trace::{get_duration, is_remote_dependency_success, is_request_success, EVENT_NAME_EXCEPTION},
     uploader_quick_pulse::{self, PostOrPing},
@@ -67,6 +70,27 @@ pub struct LiveMetricsSpanProcessor<R: RuntimeChannel> {
     is_collecting: Arc<AtomicBool>,
     shared: Arc<Mutex<Shared>>,
     message_sender: R::Sender<Message>,
+    synthetic_filter: SyntheticFilter,
+}
+
+/// Recognizes synthetic traffic, which should be excluded from live metrics.
+struct SyntheticFilter {
+    detector: SharedSyntheticSourceDetector,
+    operation_contexts: Arc<OperationContexts>,
+}
+
+impl SyntheticFilter {
+    fn is_synthetic(&self, span: &SpanData) -> bool {
+        if !self.detector.exclude_from_live_metrics() {
+            return false;
+        }
+        let is_request = matches!(span.span_kind, SpanKind::Server | SpanKind::Consumer);
+        (is_request && self.detector.detect(span).is_some())
+            || self
+                .operation_contexts
+                .get(span.span_context.trace_id())
+                .is_some_and(|context| context.synthetic_source.is_some())
+    }
 }
 
 impl<R: RuntimeChannel> std::fmt::Debug for LiveMetricsSpanProcessor<R> {
@@ -92,10 +116,14 @@ impl<R: RuntimeChannel> LiveMetricsSpanProcessor<R> {
         let is_collecting_outer = Arc::new(AtomicBool::new(false));
         let is_collecting = is_collecting_outer.clone();
         let shared_outer = Arc::new(Mutex::new(Shared {
-            metrics_collector: MetricsCollector::new(),
+            metrics_collector: MetricsCollector::new(exporter.success_policy.clone()),
             resource_data: (&exporter.resource).into(),
         }));
         let shared = shared_outer.clone();
+        let synthetic_filter = SyntheticFilter {
+            detector: exporter.synthetic_source_detector.clone(),
+            operation_contexts: exporter.operation_contexts.clone(),
+        };
         runtime.spawn(Box::pin(async move {
             let mut sender = Sender::new(
                 exporter.client,
@@ -118,9 +146,11 @@ impl<R: RuntimeChannel> LiveMetricsSpanProcessor<R> {
                         let (resource_data, metrics) = {
                             let mut shared = shared.lock().unwrap();
                             let resource_data = shared.resource_data.clone();
-                            let metrics = curr_is_collecting
-                                .then(|| shared.metrics_collector.collect_and_reset())
-                                .unwrap_or_default();
+                            let metrics = if curr_is_collecting {
+                                shared.metrics_collector.collect_and_reset()
+                            } else {
+                                Vec::new()
+                            };
                             (resource_data, metrics)
                         };
                         let (next_is_collecting, next_timeout) = sender
@@ -144,6 +174,7 @@ impl<R: RuntimeChannel> LiveMetricsSpanProcessor<R> {
             is_collecting: is_collecting_outer,
             shared: shared_outer,
             message_sender,
+            synthetic_filter,
         }
     }
 }
@@ -152,6 +183,9 @@ impl<R: RuntimeChannel> SpanProcessor for LiveMetricsSpanProcessor<R> {
     fn on_start(&self, _span: &mut Span, _cx: &Context) {}
 
     fn on_end(&self, span: SpanData) {
+        if self.synthetic_filter.is_synthetic(&span) {
+            return;
+        }
         if self.is_collecting.load(Ordering::SeqCst) {
             self.shared
                 .lock()
@@ -326,10 +360,11 @@ struct MetricsCollector {
     dependency_duration: Duration,
     exception_count: usize,
     last_collection_time: SystemTime,
+    success_policy: SharedSuccessPolicy,
 }
 
 impl MetricsCollector {
-    fn new() -> Self {
+    fn new(success_policy: SharedSuccessPolicy) -> Self {
         Self {
             system: System::new(),
             system_refresh_kind: RefreshKind::nothing()
@@ -343,6 +378,7 @@ impl MetricsCollector {
             dependency_duration: Duration::default(),
             exception_count: 0,
             last_collection_time: SystemTime::now(),
+            success_policy,
         }
     }
 
@@ -362,14 +398,15 @@ impl MetricsCollector {
         match span.span_kind {
             SpanKind::Server | SpanKind::Consumer => {
                 self.request_count += 1;
-                if !is_request_success(&span) {
+                if !is_request_success(&span, &self.success_policy.get()) {
                     self.request_failed_count += 1;
                 }
                 self.request_duration += get_duration(&span);
             }
             SpanKind::Client | SpanKind::Producer | SpanKind::Internal => {
                 self.dependency_count += 1;
-                if let Some(false) = is_remote_dependency_success(&span) {
+                if let Some(false) = is_remote_dependency_success(&span, &self.success_policy.get())
+                {
                     self.dependency_failed_count += 1;
                 }
                 self.dependency_duration += get_duration(&span);
@@ -0,0 +1,583 @@
+use crate::HttpClient;
+use backon::{FuturesTimerSleeper, Sleeper as _};
+use bytes::Bytes;
+use http::{Request, Uri};
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::{resource::ResourceDetector, Resource};
+use opentelemetry_semantic_conventions as semcov;
+use serde::Deserialize;
+use std::{
+    error::Error as StdError, fmt::Debug, future::Future, pin::pin, task::Poll, time::Duration,
+};
+
+const CLOUD_PROVIDER_AZURE: &str = "azure";
+const CLOUD_PLATFORM_APP_SERVICE: &str = "azure_app_service";
+const CLOUD_PLATFORM_FUNCTIONS: &str = "azure_functions";
+const CLOUD_PLATFORM_CONTAINER_APPS: &str = "azure_container_apps";
+const CLOUD_PLATFORM_AKS: &str = "azure_aks";
+const CLOUD_PLATFORM_VM: &str = "azure_vm";
+
+const IMDS_DEFAULT_ENDPOINT: &str = "http://169.254.169.254";
+const IMDS_COMPUTE_PATH: &str = "/metadata/instance/compute?api-version=2021-12-13&format=json";
+const IMDS_DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+const AZURE_VM_NAME: &str = "azure.vm.name";
+const AZURE_VM_SIZE: &str = "azure.vm.size";
+pub(crate) const AZURE_VM_SCALESET_NAME: &str = "azure.vm.scaleset.name";
+const AZURE_RESOURCEGROUP_NAME: &str = "azure.resourcegroup.name";
+
+const WEBSITE_SITE_NAME: &str = "WEBSITE_SITE_NAME";
+const WEBSITE_INSTANCE_ID: &str = "WEBSITE_INSTANCE_ID";
+const WEBSITE_OWNER_NAME: &str = "WEBSITE_OWNER_NAME";
+const WEBSITE_RESOURCE_GROUP: &str = "WEBSITE_RESOURCE_GROUP";
+const WEBSITE_SLOT_NAME: &str = "WEBSITE_SLOT_NAME";
+const WEBSITE_MEMORY_LIMIT_MB: &str = "WEBSITE_MEMORY_LIMIT_MB";
+const REGION_NAME: &str = "REGION_NAME";
+const FUNCTIONS_WORKER_RUNTIME: &str = "FUNCTIONS_WORKER_RUNTIME";
+const CONTAINER_APP_NAME: &str = "CONTAINER_APP_NAME";
+const CONTAINER_APP_REPLICA_NAME: &str = "CONTAINER_APP_REPLICA_NAME";
+const KUBERNETES_SERVICE_HOST: &str = "KUBERNETES_SERVICE_HOST";
+/// Domain of the API servers of AKS clusters. AKS sets `KUBERNETES_SERVICE_HOST` to the FQDN of
+/// the API server in pods.
+const AKS_API_SERVER_DOMAIN: &str = ".azmk8s.io";
+const HOSTNAME: &str = "HOSTNAME";
+const POD_NAMESPACE: &str = "POD_NAMESPACE";
+const OTEL_SERVICE_NAME: &str = "OTEL_SERVICE_NAME";
+const OTEL_RESOURCE_ATTRIBUTES: &str = "OTEL_RESOURCE_ATTRIBUTES";
+
+/// Resource detector for Azure App Service, Azure Functions, Azure Container Apps and Azure
+/// Kubernetes Service (AKS).
+///
+/// The detector reads the environment variables set by these platforms and fills in
+/// `service.name`, `service.instance.id`, `cloud.*` and, depending on the platform, `faas.*` or
+/// `k8s.*` resource attributes. This sets the cloud role and cloud role instance in Application
+/// Insights. The `service.name` is left alone if it is configured with `OTEL_SERVICE_NAME` or
+/// `OTEL_RESOURCE_ATTRIBUTES`.
+///
+/// In Kubernetes pods the pod name is read from `HOSTNAME` and the namespace from `POD_NAMESPACE`,
+/// if it is set (e.g. with the downward API). The cluster is detected as AKS, if
+/// `KUBERNETES_SERVICE_HOST` is the FQDN of an AKS API server (`*.azmk8s.io`). AKS only sets this
+/// FQDN for some cluster configurations, so use [`with_assume_aks`](Self::with_assume_aks) if
+/// all your Kubernetes clusters are AKS clusters.
+///
+/// Attributes set on the resource builder after the detector take precedence.
+///
+/// ```
+/// use opentelemetry_application_insights::AzureResourceDetector;
+/// use opentelemetry_sdk::Resource;
+///
+/// let resource = Resource::builder()
+///     .with_detector(Box::new(AzureResourceDetector::new()))
+///     .build();
+/// ```
+#[derive(Debug, Default)]
+pub struct AzureResourceDetector {
+    assume_aks: bool,
+}
+
+impl AzureResourceDetector {
+    /// Create a new Azure resource detector.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether all Kubernetes clusters are assumed to be AKS clusters.
+    ///
+    /// Default: false.
+    pub fn with_assume_aks(mut self, assume_aks: bool) -> Self {
+        self.assume_aks = assume_aks;
+        self
+    }
+}
+
+impl ResourceDetector for AzureResourceDetector {
+    fn detect(&self) -> Resource {
+        Resource::builder_empty()
+            .with_attributes(detect_from_env(
+                |name| std::env::var(name).ok(),
+                self.assume_aks,
+            ))
+            .build()
+    }
+}
+
+fn detect_from_env(env: impl Fn(&str) -> Option<String>, assume_aks: bool) -> Vec<KeyValue> {
+    let mut attrs = Vec::new();
+    let has_service_name = env(OTEL_SERVICE_NAME).is_some()
+        || env(OTEL_RESOURCE_ATTRIBUTES)
+            .is_some_and(|x| x.split(',').any(|x| x.trim().starts_with("service.name=")));
+    let service_name = |attrs: &mut Vec<KeyValue>, name: String| {
+        if !has_service_name {
+            attrs.push(KeyValue::new(semcov::resource::SERVICE_NAME, name));
+        }
+    };
+
+    if let Some(site_name) = env(WEBSITE_SITE_NAME) {
+        let is_functions = env(FUNCTIONS_WORKER_RUNTIME).is_some();
+        attrs.push(KeyValue::new(
+            semcov::resource::CLOUD_PROVIDER,
+            CLOUD_PROVIDER_AZURE,
+        ));
+        attrs.push(KeyValue::new(
+            semcov::resource::CLOUD_PLATFORM,
+            if is_functions {
+                CLOUD_PLATFORM_FUNCTIONS
+            } else {
+                CLOUD_PLATFORM_APP_SERVICE
+            },
+        ));
+        service_name(&mut attrs, site_name.clone());
+        if let Some(region) = env(REGION_NAME) {
+            attrs.push(KeyValue::new(semcov::resource::CLOUD_REGION, region));
+        }
+        if let Some(resource_id) = get_app_service_resource_id(&env, &site_name) {
+            attrs.push(KeyValue::new(
+                semcov::resource::CLOUD_RESOURCE_ID,
+                resource_id,
+            ));
+        }
+        if let Some(instance_id) = env(WEBSITE_INSTANCE_ID) {
+            if is_functions {
+                attrs.push(KeyValue::new(
+                    semcov::resource::FAAS_INSTANCE,
+                    instance_id.clone(),
+                ));
+            }
+            attrs.push(KeyValue::new(
+                semcov::resource::SERVICE_INSTANCE_ID,
+                instance_id,
+            ));
+        }
+        if is_functions {
+            attrs.push(KeyValue::new(semcov::resource::FAAS_NAME, site_name));
+            if let Some(memory) = env(WEBSITE_MEMORY_LIMIT_MB).and_then(|x| x.parse::<i64>().ok()) {
+                attrs.push(KeyValue::new(
+                    semcov::resource::FAAS_MAX_MEMORY,
+                    memory * 1024 * 1024,
+                ));
+            }
+        } else if let Some(slot_name) = env(WEBSITE_SLOT_NAME) {
+            attrs.push(KeyValue::new(
+                semcov::resource::DEPLOYMENT_ENVIRONMENT_NAME,
+                slot_name,
+            ));
+        }
+    } else if let Some(app_name) = env(CONTAINER_APP_NAME) {
+        attrs.push(KeyValue::new(
+            semcov::resource::CLOUD_PROVIDER,
+            CLOUD_PROVIDER_AZURE,
+        ));
+        attrs.push(KeyValue::new(
+            semcov::resource::CLOUD_PLATFORM,
+            CLOUD_PLATFORM_CONTAINER_APPS,
+        ));
+        service_name(&mut attrs, app_name);
+        if let Some(replica_name) = env(CONTAINER_APP_REPLICA_NAME) {
+            attrs.push(KeyValue::new(
+                semcov::resource::SERVICE_INSTANCE_ID,
+                replica_name,
+            ));
+        }
+    } else if let Some(service_host) = env(KUBERNETES_SERVICE_HOST) {
+        if assume_aks || service_host.ends_with(AKS_API_SERVER_DOMAIN) {
+            attrs.push(KeyValue::new(
+                semcov::resource::CLOUD_PROVIDER,
+                CLOUD_PROVIDER_AZURE,
+            ));
+            attrs.push(KeyValue::new(
+                semcov::resource::CLOUD_PLATFORM,
+                CLOUD_PLATFORM_AKS,
+            ));
+        }
+        if let Some(pod_name) = env(HOSTNAME) {
+            attrs.push(KeyValue::new(semcov::resource::K8S_POD_NAME, pod_name));
+        }
+        if let Some(namespace) = env(POD_NAMESPACE) {
+            attrs.push(KeyValue::new(
+                semcov::resource::K8S_NAMESPACE_NAME,
+                namespace,
+            ));
+        }
+    }
+
+    attrs
+}
+
+/// Resource id of an App Service or Functions app in the format
+/// `/subscriptions/<id>/resourceGroups/<group>/providers/Microsoft.Web/sites/<name>`.
+///
+/// `WEBSITE_OWNER_NAME` has the format `<subscription id>+<resource group>-<region>webspace`.
+fn get_app_service_resource_id(
+    env: &impl Fn(&str) -> Option<String>,
+    site_name: &str,
+) -> Option<String> {
+    let owner_name = env(WEBSITE_OWNER_NAME)?;
+    let subscription_id = owner_name.split('+').next()?;
+    let resource_group = env(WEBSITE_RESOURCE_GROUP)?;
+    Some(format!(
+        "/subscriptions/{}/resourceGroups/{}/providers/Microsoft.Web/sites/{}",
+        subscription_id, resource_group, site_name
+    ))
+}
+
+/// Resource detector for Azure virtual machines and virtual machine scale sets.
+///
+/// The detector queries the [Azure Instance Metadata Service] (IMDS) and fills in the following
+/// resource attributes: `cloud.provider`, `cloud.platform`, `cloud.region`, `cloud.account.id`,
+/// `cloud.resource_id`, `host.id`, `host.name` (the name of the VM or scale set instance),
+/// `host.type`, `os.type`, `azure.vm.name`, `azure.vm.size`, `azure.vm.scaleset.name` and
+/// `azure.resourcegroup.name`.
+///
+/// Outside of Azure the request fails or times out quickly, in which case the detected resource is
+/// empty.
+///
+/// Unlike [`AzureResourceDetector`] this detector is asynchronous, because it uses an
+/// [`HttpClient`]. Add the detected attributes to the resource builder:
+///
+/// ```no_run
+/// use opentelemetry::KeyValue;
+/// use opentelemetry_application_insights::AzureVmResourceDetector;
+/// use opentelemetry_sdk::Resource;
+///
+/// # async fn example() {
+/// let vm_resource = AzureVmResourceDetector::new(reqwest::Client::new())
+///     .detect()
+///     .await;
+/// let resource = Resource::builder()
+///     .with_attributes(
+///         vm_resource
+///             .iter()
+///             .map(|(key, value)| KeyValue::new(key.clone(), value.clone())),
+///     )
+///     .build();
+/// # }
+/// ```
+///
+/// [Azure Instance Metadata Service]: https://learn.microsoft.com/en-us/azure/virtual-machines/instance-metadata-service
+#[derive(Debug)]
+pub struct AzureVmResourceDetector<C> {
+    client: C,
+    endpoint: Uri,
+    timeout: Duration,
+}
+
+impl<C: HttpClient> AzureVmResourceDetector<C> {
+    /// Create a new Azure VM resource detector, which uses the given HTTP client to query IMDS.
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+            endpoint: append_imds_compute(IMDS_DEFAULT_ENDPOINT)
+                .expect("default endpoint is valid"),
+            timeout: IMDS_DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Set the IMDS endpoint. This should consist of scheme and authority. The detector will call
+    /// `/metadata/instance/compute` on the specified endpoint.
+    ///
+    /// Default: <http://169.254.169.254>
+    pub fn with_endpoint(
+        mut self,
+        endpoint: &str,
+    ) -> Result<Self, Box<dyn StdError + Send + Sync + 'static>> {
+        self.endpoint = append_imds_compute(endpoint)?;
+        Ok(self)
+    }
+
+    /// Set the timeout for the IMDS request.
+    ///
+    /// Default: 2 seconds.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Query IMDS and return the detected resource. The resource is empty, if IMDS is not
+    /// available.
+    pub async fn detect(&self) -> Resource {
+        let attributes = match with_timeout(self.fetch_compute(), self.timeout).await {
+            Some(Ok(compute)) => compute.into_attributes(),
+            Some(Err(err)) => {
+                opentelemetry::otel_debug!(name: "ApplicationInsights.AzureVm.DetectFailed", error = err.to_string());
+                Vec::new()
+            }
+            None => {
+                opentelemetry::otel_debug!(name: "ApplicationInsights.AzureVm.DetectTimedOut");
+                Vec::new()
+            }
+        };
+        Resource::builder_empty()
+            .with_attributes(attributes)
+            .build()
+    }
+
+    async fn fetch_compute(
+        &self,
+    ) -> Result<ImdsCompute, Box<dyn StdError + Send + Sync + 'static>> {
+        let request = Request::get(self.endpoint.clone())
+            .header("Metadata", "true")
+            .body(Bytes::new())?;
+        let response = self.client.send_bytes(request).await?;
+        if !response.status().is_success() {
+            return Err(format!("IMDS responded with status {}", response.status()).into());
+        }
+        Ok(serde_json::from_slice(response.body())?)
+    }
+}
+
+fn append_imds_compute(endpoint: &str) -> Result<Uri, http::uri::InvalidUri> {
+    format!("{}{}", endpoint.trim_end_matches('/'), IMDS_COMPUTE_PATH).parse()
+}
+
+/// Resolve to the output of the future or `None` if it did not complete within the timeout.
+async fn with_timeout<F: Future>(future: F, timeout: Duration) -> Option<F::Output> {
+    let mut future = pin!(future);
+    let mut delay = pin!(FuturesTimerSleeper.sleep(timeout));
+    std::future::poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        delay.as_mut().poll(cx).map(|_| None)
+    })
+    .await
+}
+
+/// Compute metadata of a virtual machine returned by IMDS.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct ImdsCompute {
+    location: String,
+    name: String,
+    vm_id: String,
+    vm_size: String,
+    os_type: String,
+    subscription_id: String,
+    resource_group_name: String,
+    resource_id: String,
+    vm_scale_set_name: String,
+}
+
+impl ImdsCompute {
+    fn into_attributes(self) -> Vec<KeyValue> {
+        let mut attrs = vec![
+            KeyValue::new(semcov::resource::CLOUD_PROVIDER, CLOUD_PROVIDER_AZURE),
+            KeyValue::new(semcov::resource::CLOUD_PLATFORM, CLOUD_PLATFORM_VM),
+        ];
+        let mut push = |key: &'static str, value: String| {
+            if !value.is_empty() {
+                attrs.push(KeyValue::new(key, value));
+            }
+        };
+        push(semcov::resource::CLOUD_REGION, self.location);
+        push(semcov::resource::CLOUD_ACCOUNT_ID, self.subscription_id);
+        push(semcov::resource::CLOUD_RESOURCE_ID, self.resource_id);
+        push(semcov::resource::HOST_ID, self.vm_id);
+        push(semcov::resource::HOST_NAME, self.name.clone());
+        push(semcov::resource::HOST_TYPE, self.vm_size.clone());
+        push(semcov::resource::OS_TYPE, self.os_type.to_lowercase());
+        push(AZURE_VM_NAME, self.name);
+        push(AZURE_VM_SIZE, self.vm_size);
+        push(AZURE_VM_SCALESET_NAME, self.vm_scale_set_name);
+        push(AZURE_RESOURCEGROUP_NAME, self.resource_group_name);
+        attrs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use http::Response;
+    use opentelemetry_http::HttpError;
+    use std::{collections::HashMap, sync::Mutex};
+
+    fn detect(env: &[(&str, &str)]) -> HashMap<String, String> {
+        detect_with(env, false)
+    }
+
+    fn detect_with(env: &[(&str, &str)], assume_aks: bool) -> HashMap<String, String> {
+        let env: HashMap<_, _> = env.iter().copied().collect();
+        detect_from_env(|name| env.get(name).map(|x| x.to_string()), assume_aks)
+            .into_iter()
+            .map(|kv| (kv.key.to_string(), kv.value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn app_service() {
+        let attrs = detect(&[
+            (WEBSITE_SITE_NAME, "my-app"),
+            (WEBSITE_INSTANCE_ID, "instance"),
+            (WEBSITE_OWNER_NAME, "sub-id+my-group-WestEuropewebspace"),
+            (WEBSITE_RESOURCE_GROUP, "my-group"),
+            (WEBSITE_SLOT_NAME, "production"),
+            (REGION_NAME, "West Europe"),
+        ]);
+        assert_eq!(attrs["cloud.platform"], "azure_app_service");
+        assert_eq!(attrs["service.name"], "my-app");
+        assert_eq!(attrs["service.instance.id"], "instance");
+        assert_eq!(attrs["cloud.region"], "West Europe");
+        assert_eq!(attrs["deployment.environment.name"], "production");
+        assert_eq!(
+            attrs["cloud.resource_id"],
+            "/subscriptions/sub-id/resourceGroups/my-group/providers/Microsoft.Web/sites/my-app"
+        );
+        assert!(!attrs.contains_key("faas.name"));
+    }
+
+    #[test]
+    fn functions() {
+        let attrs = detect(&[
+            (WEBSITE_SITE_NAME, "my-func"),
+            (WEBSITE_INSTANCE_ID, "instance"),
+            (WEBSITE_MEMORY_LIMIT_MB, "1536"),
+            (FUNCTIONS_WORKER_RUNTIME, "custom"),
+            (OTEL_SERVICE_NAME, "configured"),
+        ]);
+        assert_eq!(attrs["cloud.platform"], "azure_functions");
+        assert_eq!(attrs["faas.name"], "my-func");
+        assert_eq!(attrs["faas.instance"], "instance");
+        assert_eq!(attrs["faas.max_memory"], "1610612736");
+        assert!(!attrs.contains_key("service.name"));
+    }
+
+    #[test]
+    fn container_apps() {
+        let attrs = detect(&[
+            (CONTAINER_APP_NAME, "my-app"),
+            (CONTAINER_APP_REPLICA_NAME, "my-app--abc-123"),
+            (OTEL_RESOURCE_ATTRIBUTES, "deployment.environment.name=prod"),
+        ]);
+        assert_eq!(attrs["cloud.platform"], "azure_container_apps");
+        assert_eq!(attrs["service.name"], "my-app");
+        assert_eq!(attrs["service.instance.id"], "my-app--abc-123");
+    }
+
+    #[test]
+    fn aks() {
+        let attrs = detect(&[
+            (
+                KUBERNETES_SERVICE_HOST,
+                "my-cluster-dns-a1b2c3d4.hcp.westeurope.azmk8s.io",
+            ),
+            (HOSTNAME, "my-app-5d8f7c-x2k9p"),
+        ]);
+        assert_eq!(attrs["cloud.platform"], "azure_aks");
+        assert_eq!(attrs["k8s.pod.name"], "my-app-5d8f7c-x2k9p");
+        assert!(!attrs.contains_key("service.name"));
+    }
+
+    #[test]
+    fn other_kubernetes() {
+        let env = [
+            (KUBERNETES_SERVICE_HOST, "10.0.0.1"),
+            (HOSTNAME, "my-app-5d8f7c-x2k9p"),
+            (POD_NAMESPACE, "default"),
+        ];
+        let attrs = detect(&env);
+        assert!(!attrs.contains_key("cloud.provider"));
+        assert!(!attrs.contains_key("cloud.platform"));
+        assert_eq!(attrs["k8s.pod.name"], "my-app-5d8f7c-x2k9p");
+        assert_eq!(attrs["k8s.namespace.name"], "default");
+
+        let attrs = detect_with(&env, true);
+        assert_eq!(attrs["cloud.platform"], "azure_aks");
+    }
+
+    #[test]
+    fn not_azure() {
+        assert!(detect(&[(HOSTNAME, "laptop")]).is_empty());
+    }
+
+    #[derive(Debug)]
+    struct FakeImds {
+        response: Option<(u16, &'static str)>,
+        requests: Mutex<Vec<Request<Bytes>>>,
+    }
+
+    impl FakeImds {
+        fn new(response: Option<(u16, &'static str)>) -> Self {
+            Self {
+                response,
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl HttpClient for FakeImds {
+        async fn send_bytes(&self, req: Request<Bytes>) -> Result<Response<Bytes>, HttpError> {
+            self.requests.lock().unwrap().push(req);
+            match self.response {
+                Some((status, body)) => Ok(Response::builder()
+                    .status(status)
+                    .body(Bytes::from_static(body.as_bytes()))
+                    .expect("response is well formed")),
+                None => std::future::pending().await,
+            }
+        }
+    }
+
+    fn resource_to_map(resource: &Resource) -> HashMap<String, String> {
+        resource
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn vm_scale_set() {
+        let detector = AzureVmResourceDetector::new(FakeImds::new(Some((
+            200,
+            r#"{
+                "location": "westeurope",
+                "name": "my-vmss_2",
+                "osType": "Linux",
+                "resourceGroupName": "my-group",
+                "resourceId": "/subscriptions/sub-id/resourceGroups/my-group/providers/Microsoft.Compute/virtualMachineScaleSets/my-vmss/virtualMachines/2",
+                "subscriptionId": "sub-id",
+                "vmId": "02aab8a4-74ef-476e-8182-f6d2ba4166a6",
+                "vmScaleSetName": "my-vmss",
+                "vmSize": "Standard_D2s_v3",
+                "zone": "1"
+            }"#,
+        ))))
+        .with_endpoint("http://localhost:8080/")
+        .unwrap();
+        let attrs = resource_to_map(&detector.detect().await);
+        assert_eq!(attrs["cloud.platform"], "azure_vm");
+        assert_eq!(attrs["cloud.region"], "westeurope");
+        assert_eq!(attrs["host.id"], "02aab8a4-74ef-476e-8182-f6d2ba4166a6");
+        assert_eq!(attrs["host.name"], "my-vmss_2");
+        assert_eq!(attrs["os.type"], "linux");
+        assert_eq!(attrs["azure.vm.scaleset.name"], "my-vmss");
+
+        let requests = detector.client.requests.lock().unwrap();
+        assert_eq!(
+            requests[0].uri().to_string(),
+            "http://localhost:8080/metadata/instance/compute?api-version=2021-12-13&format=json"
+        );
+        assert_eq!(requests[0].headers()["Metadata"], "true");
+    }
+
+    #[tokio::test]
+    async fn vm_without_scale_set() {
+        let detector = AzureVmResourceDetector::new(FakeImds::new(Some((
+            200,
+            r#"{"name": "my-vm", "vmScaleSetName": ""}"#,
+        ))));
+        let attrs = resource_to_map(&detector.detect().await);
+        assert_eq!(attrs["azure.vm.name"], "my-vm");
+        assert!(!attrs.contains_key("azure.vm.scaleset.name"));
+    }
+
+    #[tokio::test]
+    async fn not_vm() {
+        let detector = AzureVmResourceDetector::new(FakeImds::new(Some((404, ""))));
+        assert!(detector.detect().await.is_empty());
+
+        let detector = AzureVmResourceDetector::new(FakeImds::new(None))
+            .with_timeout(Duration::from_millis(10));
+        assert!(detector.detect().await.is_empty());
+    }
+}
@@ -0,0 +1,476 @@
+//! Parsing of stack traces into structured stack frames.
+//!
+//! Application Insights shows clickable frames and groups exceptions based on the `parsedStack`
+//! field. The primary target is the output of Rust's `std::backtrace::Backtrace` and the
+//! `backtrace` crate, both in their `Display` and `Debug` formats. The formats of .NET, Java,
+//! Python and JavaScript (V8) are parsed on a best-effort basis, since exceptions may be
+//! forwarded from other processes.
+
+use crate::models::StackFrame;
+
+/// Maximum size of all frames of a parsed stack. Same as in the official SDKs.
+const MAX_PARSED_STACK_LENGTH: usize = 32768;
+
+/// Estimated size of a serialized frame in addition to method, assembly and file name (field
+/// names, level and line).
+const FRAME_OVERHEAD: usize = 58;
+
+pub(crate) struct ParsedStack {
+    pub(crate) frames: Vec<StackFrame>,
+    pub(crate) has_full_stack: bool,
+}
+
+/// Parse a stack trace. Returns `None` if no frames could be found.
+pub(crate) fn parse_stack_trace(stack: &str) -> Option<ParsedStack> {
+    let mut frames = match stack.trim_start().strip_prefix("Backtrace [") {
+        Some(frames) => parse_rust_debug(frames),
+        None => parse_lines(stack),
+    };
+    if frames.is_empty() {
+        return None;
+    }
+    for (level, frame) in frames.iter_mut().enumerate() {
+        frame.level = level as i32;
+    }
+    Some(limit_size(frames))
+}
+
+/// Parse the `Debug` format of `std::backtrace::Backtrace`, e.g.
+///
+/// ```plain
+/// Backtrace [{ fn: "app::main", file: "./src/main.rs", line: 10 }, { fn: "__libc_start_main" }]
+/// ```
+fn parse_rust_debug(frames: &str) -> Vec<StackFrame> {
+    frames
+        .split("{ fn: \"")
+        .skip(1)
+        .filter_map(|frame| {
+            let (symbol, rest) = frame.split_once('"')?;
+            let file = rest
+                .split_once("file: \"")
+                .and_then(|(_, file)| file.split_once('"'))
+                .map(|(file, _)| file);
+            let line = rest.split_once("line: ").and_then(|(_, line)| {
+                line.split(|c: char| !c.is_ascii_digit())
+                    .next()
+                    .and_then(|line| line.parse().ok())
+            });
+            Some(rust_frame(symbol, file, line))
+        })
+        .collect()
+}
+
+/// Parse the lines of a stack trace. Only stacks starting with the first frame of a Rust backtrace
+/// (`0: ...`) are parsed as such, since lines of other stacks may look like Rust frames, too (e.g.
+/// `404: Not Found` in an error message).
+fn parse_lines(stack: &str) -> Vec<StackFrame> {
+    let first_line = stack.lines().map(str::trim).find(|line| !line.is_empty());
+    if first_line.is_some_and(|line| line.starts_with("0:") && parse_rust_header(line).is_some()) {
+        parse_rust_display(stack)
+    } else {
+        parse_other(stack)
+    }
+}
+
+/// Parse the `Display` format of `std::backtrace::Backtrace` and the `backtrace` crate, e.g.
+///
+/// ```plain
+///    0: app::do_something
+///              at ./src/main.rs:5:5
+///    1: app::main
+///              at ./src/main.rs:10:5
+/// ```
+///
+/// Inlined functions are printed without a frame number.
+fn parse_rust_display(stack: &str) -> Vec<StackFrame> {
+    let mut frames: Vec<StackFrame> = Vec::new();
+    for raw_line in stack.lines() {
+        let line = raw_line.trim();
+        if let Some(symbol) = parse_rust_header(line) {
+            frames.push(rust_frame(symbol, None, None));
+        } else if let Some(location) = line.strip_prefix("at ") {
+            if let Some(frame) = frames.last_mut().filter(|frame| frame.file_name.is_none()) {
+                let (file, line) = parse_location(location);
+                frame.file_name = file.map(Into::into);
+                frame.line = line;
+            }
+        } else if !line.is_empty() && raw_line.starts_with(' ') && !frames.is_empty() {
+            frames.push(rust_frame(line, None, None));
+        }
+    }
+    frames
+}
+
+/// Symbol of a line like `   0: app::main` or `   0:     0x55d8a8e8b6f9 - app::main`.
+fn parse_rust_header(line: &str) -> Option<&str> {
+    let (index, symbol) = line.split_once(':')?;
+    if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let symbol = symbol.trim();
+    match symbol.split_once(" - ") {
+        Some((address, symbol)) if address.starts_with("0x") => Some(symbol),
+        _ => Some(symbol).filter(|symbol| !symbol.is_empty()),
+    }
+}
+
+fn rust_frame(symbol: &str, file: Option<&str>, line: Option<i32>) -> StackFrame {
+    let method = strip_rust_hash(symbol);
+    let assembly = method
+        .split_once("::")
+        .map(|(krate, _)| krate)
+        .filter(|krate| {
+            !krate.is_empty() && krate.chars().all(|c| c.is_alphanumeric() || c == '_')
+        });
+    frame(method, assembly, file, line)
+}
+
+/// Remove the hash of legacy mangled symbols, e.g. `app::main::h0123456789abcdef`.
+fn strip_rust_hash(symbol: &str) -> &str {
+    match symbol.rsplit_once("::h") {
+        Some((name, hash)) if hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            name
+        }
+        _ => symbol,
+    }
+}
+
+/// Parse .NET, Java, JavaScript and Python stack traces line by line.
+fn parse_other(stack: &str) -> Vec<StackFrame> {
+    let mut frames = Vec::new();
+    let mut is_python = false;
+    for line in stack.lines().map(str::trim) {
+        if let Some(frame) = line.strip_prefix("at ").and_then(parse_at_line) {
+            frames.push(frame);
+        } else if let Some(frame) = parse_python_line(line) {
+            is_python = true;
+            frames.push(frame);
+        }
+    }
+    if is_python {
+        // Python prints the most recent call last.
+        frames.reverse();
+    }
+    frames
+}
+
+/// Parse a line starting with `at `, e.g.
+///
+/// - .NET: `at Namespace.Class.Method(String s) in C:\src\File.cs:line 42`
+/// - Java: `at com.example.Class.method(Class.java:42)`
+/// - JavaScript: `at method (/src/file.js:42:13)` or `at /src/file.js:42:13`
+///
+/// Returns `None` for lines which don't look like a frame.
+fn parse_at_line(line: &str) -> Option<StackFrame> {
+    if let Some((method, location)) = line.split_once(" in ") {
+        if let Some((file, line)) = location.rsplit_once(":line ") {
+            return Some(frame(method, None, Some(file), line.parse().ok()));
+        }
+    }
+
+    if let Some(rest) = line.strip_suffix(')') {
+        let (method, inner) = rest.rsplit_once('(')?;
+        if method.ends_with(' ') {
+            let (file, line) = parse_location(inner);
+            return Some(frame(method.trim(), None, file, line));
+        }
+        if let (Some(file), Some(line)) = parse_location(inner) {
+            return Some(frame(method, None, Some(file), Some(line)));
+        }
+        if inner == "Native Method" || inner == "Unknown Source" {
+            return Some(frame(method, None, None, None));
+        }
+        // .NET method without file, e.g. `App.Program.Main(String[] args)`.
+        if method.contains('.') && !method.contains(char::is_whitespace) {
+            return Some(frame(line, None, None, None));
+        }
+        return None;
+    }
+
+    match parse_location(line) {
+        (file, Some(line)) => Some(frame("<anonymous>", None, file, Some(line))),
+        _ => None,
+    }
+}
+
+/// Parse a line like `File "/src/app.py", line 42, in method`.
+fn parse_python_line(line: &str) -> Option<StackFrame> {
+    let (file, rest) = line.strip_prefix("File \"")?.split_once('"')?;
+    let rest = rest.strip_prefix(", line ")?;
+    let (line, method) = rest.split_once(", in ").unwrap_or((rest, "<module>"));
+    Some(frame(method, None, Some(file), line.parse().ok()))
+}
+
+/// Split a location like `file:line:column` or `file:line` into file and line.
+fn parse_location(location: &str) -> (Option<&str>, Option<i32>) {
+    let mut file = location;
+    let mut numbers = Vec::with_capacity(2);
+    while numbers.len() < 2 {
+        match file.rsplit_once(':') {
+            Some((rest, number))
+                if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                numbers.push(number);
+                file = rest;
+            }
+            _ => break,
+        }
+    }
+    let line = numbers.last().and_then(|line| line.parse().ok());
+    (Some(file).filter(|file| !file.is_empty()), line)
+}
+
+fn frame(
+    method: &str,
+    assembly: Option<&str>,
+    file: Option<&str>,
+    line: Option<i32>,
+) -> StackFrame {
+    StackFrame {
+        level: 0,
+        method: method.into(),
+        assembly: assembly.map(Into::into),
+        file_name: file.map(Into::into),
+        line,
+    }
+}
+
+/// Keep frames from the top and bottom of the stack, which tend to be the most useful ones, and
+/// drop frames from the middle if the stack is too large.
+fn limit_size(mut frames: Vec<StackFrame>) -> ParsedStack {
+    let sizes: Vec<usize> = frames.iter().map(frame_size).collect();
+    if sizes.iter().sum::<usize>() <= MAX_PARSED_STACK_LENGTH {
+        return ParsedStack {
+            frames,
+            has_full_stack: true,
+        };
+    }
+
+    let mut total = 0;
+    let (mut front, mut back) = (0, frames.len());
+    let mut take_front = true;
+    while front < back {
+        let index = if take_front { front } else { back - 1 };
+        if total + sizes[index] > MAX_PARSED_STACK_LENGTH {
+            break;
+        }
+        total += sizes[index];
+        if take_front {
+            front += 1;
+        } else {
+            back -= 1;
+        }
+        take_front = !take_front;
+    }
+    let bottom = frames.split_off(back);
+    frames.truncate(front);
+    frames.extend(bottom);
+    ParsedStack {
+        frames,
+        has_full_stack: false,
+    }
+}
+
+fn frame_size(frame: &StackFrame) -> usize {
+    frame.method.as_ref().len()
+        + frame.assembly.as_ref().map_or(0, |x| x.as_ref().len())
+        + frame.file_name.as_ref().map_or(0, |x| x.as_ref().len())
+        + FRAME_OVERHEAD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type SimpleFrame = (i32, String, Option<String>, Option<String>, Option<i32>);
+
+    fn simplified(stack: &str) -> Vec<SimpleFrame> {
+        parse_stack_trace(stack)
+            .expect("frames")
+            .frames
+            .into_iter()
+            .map(|frame| {
+                (
+                    frame.level,
+                    frame.method.as_ref().to_string(),
+                    frame.assembly.map(|x| x.as_ref().to_string()),
+                    frame.file_name.map(|x| x.as_ref().to_string()),
+                    frame.line,
+                )
+            })
+            .collect()
+    }
+
+    fn s(value: &str) -> Option<String> {
+        Some(value.to_string())
+    }
+
+    #[test]
+    fn rust_display() {
+        let stack = "   0: app::do_something
+             at ./src/main.rs:5:5
+      core::ops::function::FnOnce::call_once
+             at /rustc/abc/library/core/src/ops/function.rs:250:5
+   1:     0x55d8a8e8b6f9 - app::main::h0123456789abcdef
+   2: __libc_start_main
+note: Some details are omitted, run with `RUST_BACKTRACE=full` for a verbose backtrace.";
+        assert_eq!(
+            simplified(stack),
+            vec![
+                (
+                    0,
+                    "app::do_something".into(),
+                    s("app"),
+                    s("./src/main.rs"),
+                    Some(5)
+                ),
+                (
+                    1,
+                    "core::ops::function::FnOnce::call_once".into(),
+                    s("core"),
+                    s("/rustc/abc/library/core/src/ops/function.rs"),
+                    Some(250)
+                ),
+                (2, "app::main".into(), s("app"), None, None),
+                (3, "__libc_start_main".into(), None, None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn rust_debug() {
+        let stack = r#"Backtrace [{ fn: "app::main", file: "./src/main.rs", line: 10 }, { fn: "<T as core::any::Any>::type_id" }]"#;
+        assert_eq!(
+            simplified(stack),
+            vec![
+                (
+                    0,
+                    "app::main".into(),
+                    s("app"),
+                    s("./src/main.rs"),
+                    Some(10)
+                ),
+                (1, "<T as core::any::Any>::type_id".into(), None, None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn dotnet() {
+        let stack = r"System.InvalidOperationException: oops
+   at App.Service.Run(String name) in C:\src\Service.cs:line 42
+   at App.Program.Main()";
+        assert_eq!(
+            simplified(stack),
+            vec![
+                (
+                    0,
+                    "App.Service.Run(String name)".into(),
+                    None,
+                    s(r"C:\src\Service.cs"),
+                    Some(42)
+                ),
+                (1, "App.Program.Main()".into(), None, None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn java() {
+        let stack = "java.lang.IllegalStateException: oops
+	at com.example.Service.run(Service.java:42)
+	at java.base/jdk.internal.reflect.NativeMethodAccessorImpl.invoke0(Native Method)
+	... 5 more";
+        assert_eq!(
+            simplified(stack),
+            vec![
+                (
+                    0,
+                    "com.example.Service.run".into(),
+                    None,
+                    s("Service.java"),
+                    Some(42)
+                ),
+                (
+                    1,
+                    "java.base/jdk.internal.reflect.NativeMethodAccessorImpl.invoke0".into(),
+                    None,
+                    None,
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn javascript() {
+        let stack = "Error: oops
+    at run (/src/service.js:42:13)
+    at /src/index.js:1:1";
+        assert_eq!(
+            simplified(stack),
+            vec![
+                (0, "run".into(), None, s("/src/service.js"), Some(42)),
+                (1, "<anonymous>".into(), None, s("/src/index.js"), Some(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn python() {
+        let stack = r#"Traceback (most recent call last):
+  File "/src/app.py", line 10, in <module>
+    main()
+  File "/src/app.py", line 5, in main
+    raise ValueError("oops")
+ValueError: oops"#;
+        assert_eq!(
+            simplified(stack),
+            vec![
+                (0, "main".into(), None, s("/src/app.py"), Some(5)),
+                (1, "<module>".into(), None, s("/src/app.py"), Some(10)),
+            ]
+        );
+    }
+
+    #[test]
+    fn python_with_rust_like_lines() {
+        let stack = r#"Traceback (most recent call last):
+  File "/src/app.py", line 5, in main
+    raise HTTPError("404: Not Found")
+HTTPError: 404: Not Found
+12:34:56 request failed"#;
+        assert_eq!(
+            simplified(stack),
+            vec![(0, "main".into(), None, s("/src/app.py"), Some(5))]
+        );
+    }
+
+    #[test]
+    fn skips_lines_which_are_not_frames() {
+        let stack = "Error: oops
+    at run (/src/service.js:42:13)
+    at the end of the day";
+        assert_eq!(
+            simplified(stack),
+            vec![(0, "run".into(), None, s("/src/service.js"), Some(42))]
+        );
+    }
+
+    #[test]
+    fn unknown_format() {
+        assert!(parse_stack_trace("something went wrong").is_none());
+    }
+
+    #[test]
+    fn limit_size_drops_middle_frames() {
+        let stack: String = (0..1000)
+            .map(|i| format!("{:4}: app::function_{}\n at ./src/main.rs:{}:5\n", i, i, i))
+            .collect();
+        let parsed = parse_stack_trace(&stack).expect("frames");
+        assert!(!parsed.has_full_stack);
+        assert!(parsed.frames.iter().map(frame_size).sum::<usize>() <= MAX_PARSED_STACK_LENGTH);
+        assert_eq!(parsed.frames.first().unwrap().level, 0);
+        assert_eq!(parsed.frames.last().unwrap().level, 999);
+    }
+}
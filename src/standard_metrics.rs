@@ -0,0 +1,228 @@
+use crate::{
+    metrics::STANDARD_METRICS_SCOPE,
+    models::{Data, Properties},
+    operation_context::OperationContexts,
+    success::SharedSuccessPolicy,
+    synthetic::SharedSyntheticSourceDetector,
+    trace::{
+        get_dependency_target, get_dependency_type, get_duration, get_result_code, is_page_view,
+        is_remote_dependency_success, is_request_success,
+    },
+    Exporter,
+};
+use opentelemetry::{
+    metrics::{Histogram, MeterProvider as _},
+    trace::SpanKind,
+    InstrumentationScope, KeyValue, Value,
+};
+use opentelemetry_sdk::{
+    error::OTelSdkResult,
+    metrics::SdkMeterProvider,
+    trace::{SpanData, SpanProcessor},
+};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+const REQUESTS_DURATION: &str = "requests/duration";
+const DEPENDENCIES_DURATION: &str = "dependencies/duration";
+
+/// Property, which tells Application Insights that the standard metrics of a request or
+/// dependency were pre-aggregated, so they're not computed from the (sampled) telemetry.
+const PROPERTY_PROCESSED_BY_METRIC_EXTRACTORS: &str = "_MS.ProcessedByMetricExtractors";
+
+/// Span processor, which pre-aggregates the standard metrics of Application Insights from all
+/// spans, before they are sampled.
+///
+/// Application Insights computes the standard metrics (e.g. server requests, server response time
+/// and failed requests in Metrics Explorer) from the request and dependency telemetry. With
+/// sampling they're only estimated. This processor records the duration of requests and
+/// dependencies as the standard metrics `requests/duration` and `dependencies/duration` with the
+/// following dimensions, and marks the request and dependency telemetry of the exporter as
+/// pre-aggregated:
+///
+/// | Metric                  | Dimensions                                                              |
+/// | ----------------------- | ----------------------------------------------------------------------- |
+/// | `requests/duration`     | `Request.Success`, `request/resultCode`, `operation/synthetic`          |
+/// | `dependencies/duration` | `Dependency.Success`, `dependency/resultCode`, `Dependency.Type`, `dependency/target`, `operation/synthetic` |
+///
+/// The cloud role name and instance are added as dimensions, too. The metrics are sent by the
+/// exporter, so the meter provider needs a reader with the exporter (or one of its clones).
+///
+/// The processor sees all spans which are recorded, including the ones which are recorded but not
+/// sampled ([`SamplingDecision::RecordOnly`]). Synthetic requests are detected with the
+/// [`SyntheticSourceDetector`](crate::SyntheticSourceDetector) of the exporter; dependencies are
+/// only recognized as synthetic if the
+/// [`OperationContextSpanProcessor`](crate::OperationContextSpanProcessor) is used.
+///
+/// [`SamplingDecision::RecordOnly`]: opentelemetry::trace::SamplingDecision::RecordOnly
+///
+/// ```
+/// use opentelemetry_sdk::{
+///     metrics::{PeriodicReader, SdkMeterProvider},
+///     trace::SdkTracerProvider,
+/// };
+///
+/// # let http_client = reqwest::blocking::Client::new();
+/// let exporter = opentelemetry_application_insights::Exporter::new_from_connection_string(
+///     "InstrumentationKey=00000000-0000-0000-0000-000000000000",
+///     http_client,
+/// )
+/// .expect("valid connection string");
+/// let meter_provider = SdkMeterProvider::builder()
+///     .with_reader(PeriodicReader::builder(exporter.clone()).build())
+///     .build();
+/// let tracer_provider = SdkTracerProvider::builder()
+///     .with_span_processor(
+///         opentelemetry_application_insights::StandardMetricsSpanProcessor::new(
+///             &exporter,
+///             &meter_provider,
+///         ),
+///     )
+///     .with_batch_exporter(exporter)
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct StandardMetricsSpanProcessor {
+    requests_duration: Histogram<f64>,
+    dependencies_duration: Histogram<f64>,
+    success_policy: SharedSuccessPolicy,
+    synthetic_source_detector: SharedSyntheticSourceDetector,
+    operation_contexts: Arc<OperationContexts>,
+}
+
+impl StandardMetricsSpanProcessor {
+    /// Create a new standard metrics span processor, which records the metrics with the given
+    /// meter provider and marks the telemetry of the given exporter and all its clones as
+    /// pre-aggregated.
+    pub fn new<C>(exporter: &Exporter<C>, meter_provider: &SdkMeterProvider) -> Self {
+        exporter.standard_metrics.store(true, Ordering::Relaxed);
+        let meter = meter_provider
+            .meter_with_scope(InstrumentationScope::builder(STANDARD_METRICS_SCOPE).build());
+        Self {
+            requests_duration: meter
+                .f64_histogram(REQUESTS_DURATION)
+                .with_unit("ms")
+                .build(),
+            dependencies_duration: meter
+                .f64_histogram(DEPENDENCIES_DURATION)
+                .with_unit("ms")
+                .build(),
+            success_policy: exporter.success_policy.clone(),
+            synthetic_source_detector: exporter.synthetic_source_detector.clone(),
+            operation_contexts: exporter.operation_contexts.clone(),
+        }
+    }
+
+    fn is_synthetic(&self, span: &SpanData) -> bool {
+        if matches!(span.span_kind, SpanKind::Server | SpanKind::Consumer)
+            && self.synthetic_source_detector.detect(span).is_some()
+        {
+            return true;
+        }
+        self.operation_contexts
+            .get(span.span_context.trace_id())
+            .is_some_and(|context| context.synthetic_source.is_some())
+    }
+
+    fn record_request(&self, span: &SpanData, duration: f64, synthetic: bool) {
+        let span_attrs = span_attrs(span);
+        let success = is_request_success(span, &self.success_policy.get());
+        self.requests_duration.record(
+            duration,
+            &[
+                KeyValue::new("Request.Success", bool_to_str(success)),
+                KeyValue::new(
+                    "request/resultCode",
+                    get_result_code(span, &span_attrs).into_owned(),
+                ),
+                KeyValue::new("operation/synthetic", bool_to_str(synthetic)),
+            ],
+        );
+    }
+
+    fn record_dependency(&self, span: &SpanData, duration: f64, synthetic: bool) {
+        let span_attrs = span_attrs(span);
+        let success = is_remote_dependency_success(span, &self.success_policy.get());
+        let mut attrs = vec![
+            KeyValue::new("Dependency.Success", bool_to_str(success.unwrap_or(true))),
+            KeyValue::new(
+                "dependency/resultCode",
+                get_result_code(span, &span_attrs).into_owned(),
+            ),
+            KeyValue::new("operation/synthetic", bool_to_str(synthetic)),
+        ];
+        if let Some(type_) = get_dependency_type(span, &span_attrs) {
+            attrs.push(KeyValue::new("Dependency.Type", type_.into_owned()));
+        }
+        if let Some(target) = get_dependency_target(span, &span_attrs) {
+            attrs.push(KeyValue::new("dependency/target", target.into_owned()));
+        }
+        self.dependencies_duration.record(duration, &attrs);
+    }
+}
+
+fn span_attrs(span: &SpanData) -> HashMap<&str, &Value> {
+    span.attributes
+        .iter()
+        .map(|kv| (kv.key.as_str(), &kv.value))
+        .collect()
+}
+
+fn bool_to_str(value: bool) -> &'static str {
+    if value {
+        "True"
+    } else {
+        "False"
+    }
+}
+
+impl SpanProcessor for StandardMetricsSpanProcessor {
+    fn on_start(&self, _span: &mut opentelemetry_sdk::trace::Span, _cx: &opentelemetry::Context) {}
+
+    fn on_end(&self, span: SpanData) {
+        if is_page_view(&span) {
+            return;
+        }
+        let duration = get_duration(&span).as_secs_f64() * 1000.0;
+        let synthetic = self.is_synthetic(&span);
+        match span.span_kind {
+            SpanKind::Server | SpanKind::Consumer => {
+                self.record_request(&span, duration, synthetic)
+            }
+            SpanKind::Client | SpanKind::Producer | SpanKind::Internal => {
+                self.record_dependency(&span, duration, synthetic)
+            }
+        }
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn shutdown_with_timeout(&self, _timeout: Duration) -> OTelSdkResult {
+        Ok(())
+    }
+}
+
+/// Mark requests and dependencies as pre-aggregated, if the standard metrics span processor is
+/// used with the exporter.
+pub(crate) fn mark_processed_by_metric_extractors(standard_metrics: &AtomicBool, data: &mut Data) {
+    if !standard_metrics.load(Ordering::Relaxed) {
+        return;
+    }
+    let (properties, extractor) = match data {
+        Data::Request(data) => (&mut data.properties, "(Name:'Request', Ver:'1.1')"),
+        Data::RemoteDependency(data) => (&mut data.properties, "(Name:'Dependency', Ver:'1.1')"),
+        _ => return,
+    };
+    properties.get_or_insert_with(Properties::new).insert(
+        PROPERTY_PROCESSED_BY_METRIC_EXTRACTORS.into(),
+        extractor.into(),
+    );
+}
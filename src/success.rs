@@ -0,0 +1,230 @@
+use opentelemetry::Value;
+use opentelemetry_sdk::trace::SpanData;
+use opentelemetry_semantic_conventions as semcov;
+use std::{
+    convert::TryFrom,
+    fmt::Debug,
+    ops::RangeInclusive,
+    sync::{Arc, RwLock, RwLockReadGuard},
+};
+
+type SuccessCallback = Arc<dyn Fn(&SpanData) -> Option<bool> + Send + Sync + 'static>;
+
+/// Policy that decides whether a request or dependency was successful.
+///
+/// By default only the span status determines success (see the attribute mapping in the crate
+/// documentation). The policy can override this based on HTTP status codes, the presence of an
+/// `error.type` attribute or a custom callback. These are evaluated in the following order, and
+/// the first one that applies wins:
+///
+/// 1. The callback, if it returns `Some`.
+/// 2. The failure status code ranges, if any are configured for the telemetry type and the span
+///    has an `http.response.status_code` attribute.
+/// 3. The `error.type` attribute, if enabled and present.
+/// 4. The span status.
+///
+/// The policy also applies to the request and dependency failure rates in live metrics.
+///
+/// ```
+/// use opentelemetry_application_insights::SuccessPolicy;
+///
+/// let policy = SuccessPolicy::new()
+///     // 4xx responses are expected for our API and should not count as failures.
+///     .with_request_failure_status_codes([500..=599])
+///     .with_dependency_failure_status_codes([400..=599]);
+/// ```
+#[derive(Clone, Default)]
+pub struct SuccessPolicy {
+    request_failure_status_codes: Vec<RangeInclusive<u16>>,
+    dependency_failure_status_codes: Vec<RangeInclusive<u16>>,
+    error_type_as_failure: bool,
+    callback: Option<SuccessCallback>,
+}
+
+impl Debug for SuccessPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SuccessPolicy")
+            .field(
+                "request_failure_status_codes",
+                &self.request_failure_status_codes,
+            )
+            .field(
+                "dependency_failure_status_codes",
+                &self.dependency_failure_status_codes,
+            )
+            .field("error_type_as_failure", &self.error_type_as_failure)
+            .field("callback", &self.callback.is_some())
+            .finish()
+    }
+}
+
+impl SuccessPolicy {
+    /// Create a new policy, which only considers the span status.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set HTTP status code ranges, which mark a request (`SERVER` and `CONSUMER` spans) as failed.
+    /// All other status codes mark it as successful, regardless of the span status.
+    pub fn with_request_failure_status_codes(
+        mut self,
+        status_codes: impl IntoIterator<Item = RangeInclusive<u16>>,
+    ) -> Self {
+        self.request_failure_status_codes = status_codes.into_iter().collect();
+        self
+    }
+
+    /// Set HTTP status code ranges, which mark a dependency (`CLIENT`, `PRODUCER` and `INTERNAL`
+    /// spans) as failed. All other status codes mark it as successful, regardless of the span
+    /// status.
+    pub fn with_dependency_failure_status_codes(
+        mut self,
+        status_codes: impl IntoIterator<Item = RangeInclusive<u16>>,
+    ) -> Self {
+        self.dependency_failure_status_codes = status_codes.into_iter().collect();
+        self
+    }
+
+    /// Set whether the presence of an `error.type` attribute marks a span as failed.
+    ///
+    /// Default: false.
+    pub fn with_error_type_as_failure(mut self, error_type_as_failure: bool) -> Self {
+        self.error_type_as_failure = error_type_as_failure;
+        self
+    }
+
+    /// Set a callback, which decides whether a span was successful. Return `None` to fall back to
+    /// the other rules of this policy.
+    pub fn with_callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&SpanData) -> Option<bool> + Send + Sync + 'static,
+    {
+        self.callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Success according to this policy or `None` if the span status should decide.
+    pub(crate) fn evaluate(&self, span: &SpanData, is_request: bool) -> Option<bool> {
+        if let Some(success) = self.callback.as_ref().and_then(|callback| callback(span)) {
+            return Some(success);
+        }
+
+        let failure_status_codes = if is_request {
+            &self.request_failure_status_codes
+        } else {
+            &self.dependency_failure_status_codes
+        };
+        if !failure_status_codes.is_empty() {
+            if let Some(status_code) = get_http_status_code(span) {
+                return Some(
+                    !failure_status_codes
+                        .iter()
+                        .any(|range| range.contains(&status_code)),
+                );
+            }
+        }
+
+        if self.error_type_as_failure
+            && span
+                .attributes
+                .iter()
+                .any(|kv| kv.key.as_str() == semcov::trace::ERROR_TYPE)
+        {
+            return Some(false);
+        }
+
+        None
+    }
+}
+
+/// Success policy of an exporter, shared by all its clones and the span processors created from
+/// them, so setting the policy applies to all of them.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SharedSuccessPolicy(Arc<RwLock<SuccessPolicy>>);
+
+impl SharedSuccessPolicy {
+    pub(crate) fn set(&self, success_policy: SuccessPolicy) {
+        *self.0.write().unwrap() = success_policy;
+    }
+
+    pub(crate) fn get(&self) -> RwLockReadGuard<'_, SuccessPolicy> {
+        self.0.read().unwrap()
+    }
+}
+
+fn get_http_status_code(span: &SpanData) -> Option<u16> {
+    #[allow(deprecated)]
+    let deprecated_key = semcov::attribute::HTTP_STATUS_CODE;
+    let value = span
+        .attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == semcov::trace::HTTP_RESPONSE_STATUS_CODE)
+        .or_else(|| {
+            span.attributes
+                .iter()
+                .find(|kv| kv.key.as_str() == deprecated_key)
+        })
+        .map(|kv| &kv.value)?;
+    match value {
+        Value::I64(status_code) => u16::try_from(*status_code).ok(),
+        Value::String(status_code) => status_code.as_str().parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::test_span;
+    use opentelemetry::{trace::SpanKind, KeyValue};
+
+    #[test]
+    fn default_defers_to_status() {
+        let span = test_span(
+            SpanKind::Server,
+            vec![
+                KeyValue::new(semcov::trace::HTTP_RESPONSE_STATUS_CODE, 500),
+                KeyValue::new(semcov::trace::ERROR_TYPE, "500"),
+            ],
+        );
+        assert_eq!(SuccessPolicy::new().evaluate(&span, true), None);
+    }
+
+    #[test]
+    fn status_code_ranges() {
+        let policy = SuccessPolicy::new().with_request_failure_status_codes([500..=599]);
+        let not_found = test_span(
+            SpanKind::Server,
+            vec![KeyValue::new(semcov::trace::HTTP_RESPONSE_STATUS_CODE, 404)],
+        );
+        let unavailable = test_span(
+            SpanKind::Server,
+            vec![KeyValue::new(semcov::trace::HTTP_RESPONSE_STATUS_CODE, 503)],
+        );
+        assert_eq!(policy.evaluate(&not_found, true), Some(true));
+        assert_eq!(policy.evaluate(&unavailable, true), Some(false));
+        assert_eq!(policy.evaluate(&unavailable, false), None);
+    }
+
+    #[test]
+    fn error_type() {
+        let policy = SuccessPolicy::new().with_error_type_as_failure(true);
+        let span = test_span(
+            SpanKind::Server,
+            vec![KeyValue::new(semcov::trace::ERROR_TYPE, "timeout")],
+        );
+        assert_eq!(policy.evaluate(&span, false), Some(false));
+    }
+
+    #[test]
+    fn callback_wins() {
+        let policy = SuccessPolicy::new()
+            .with_request_failure_status_codes([400..=599])
+            .with_callback(|span| Some(span.name == "span"));
+        let span = test_span(
+            SpanKind::Server,
+            vec![KeyValue::new(semcov::trace::HTTP_RESPONSE_STATUS_CODE, 404)],
+        );
+        assert_eq!(policy.evaluate(&span, true), Some(true));
+    }
+}
@@ -0,0 +1,336 @@
+use crate::convert::attrs_to_map;
+use opentelemetry_sdk::trace::SpanData;
+use opentelemetry_semantic_conventions as semcov;
+use std::{
+    borrow::Cow,
+    fmt::Debug,
+    sync::{Arc, RwLock},
+};
+
+type SyntheticSourceCallback = Arc<dyn Fn(&SpanData) -> Option<String> + Send + Sync + 'static>;
+
+/// Synthetic source for well-known user agents of probes and bots. Patterns are matched against
+/// the tokens of the user agent, see [`tokenize`].
+const DEFAULT_USER_AGENT_PATTERNS: &[(&str, &str)] = &[
+    ("kube-probe", "Kubernetes Probe"),
+    ("alwayson", "Azure App Service AlwaysOn"),
+    ("healthcheck", "Health Check"),
+    ("elb-healthchecker", "Health Check"),
+    ("bot", "Bot"),
+    ("crawler", "Bot"),
+    ("spider", "Bot"),
+    ("googlebot", "Bot"),
+    ("bingbot", "Bot"),
+    ("yandexbot", "Bot"),
+    ("duckduckbot", "Bot"),
+    ("applebot", "Bot"),
+    ("baiduspider", "Bot"),
+];
+
+/// Synthetic source for well-known health check routes.
+const DEFAULT_ROUTES: &[(&str, &str)] = &[
+    ("/health", "Health Check"),
+    ("/healthz", "Health Check"),
+    ("/livez", "Health Check"),
+    ("/readyz", "Health Check"),
+];
+
+/// Detector for synthetic traffic, like availability tests, health checks and bots.
+///
+/// Requests (`SERVER` and `CONSUMER` spans) detected as synthetic get the synthetic source
+/// (`ai.operation.syntheticSource`) context tag, so Application Insights can tell them apart from
+/// real user traffic. Add the [`OperationContextSpanProcessor`](crate::OperationContextSpanProcessor)
+/// to the tracer provider to set the synthetic source on all telemetry of the same operation.
+///
+/// The rules are evaluated in the following order, and the first one that applies wins:
+///
+/// 1. The callback, if it returns `Some`.
+/// 2. The user agent patterns, matched against `user_agent.original`. Added patterns are checked
+///    before the default ones, in the order they were added.
+/// 3. The routes, matched against `http.route` or, if missing, `url.path`. Added routes are
+///    checked before the default ones.
+///
+/// User agent patterns match whole words, ignoring case: the user agent and the pattern are split
+/// into words at all characters other than letters and digits, and the words of the pattern must
+/// appear next to each other in the user agent. For example the pattern `kube-probe` matches
+/// `kube-probe/1.30`, and the pattern `bot` matches `Pingdom.com_bot_version_1.4` but not
+/// `robot` or `Cubot`.
+///
+/// By default the detector knows the user agents of Kubernetes probes, Azure App Service AlwaysOn
+/// and health checks, and common bots, as well as the routes `/health`, `/healthz`, `/livez` and
+/// `/readyz`.
+///
+/// ```
+/// use opentelemetry_application_insights::SyntheticSourceDetector;
+///
+/// let detector = SyntheticSourceDetector::new()
+///     .with_user_agent_pattern("pingdom", "Pingdom")
+///     .with_route("/status", "Health Check");
+/// ```
+#[derive(Clone)]
+pub struct SyntheticSourceDetector {
+    user_agent_patterns: Vec<(Vec<String>, Cow<'static, str>)>,
+    routes: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    defaults: bool,
+    callback: Option<SyntheticSourceCallback>,
+    #[cfg(feature = "live-metrics")]
+    exclude_from_live_metrics: bool,
+}
+
+impl Debug for SyntheticSourceDetector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("SyntheticSourceDetector");
+        debug
+            .field("user_agent_patterns", &self.user_agent_patterns)
+            .field("routes", &self.routes)
+            .field("defaults", &self.defaults)
+            .field("callback", &self.callback.is_some());
+        #[cfg(feature = "live-metrics")]
+        debug.field("exclude_from_live_metrics", &self.exclude_from_live_metrics);
+        debug.finish()
+    }
+}
+
+impl Default for SyntheticSourceDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyntheticSourceDetector {
+    /// Create a new detector with the default user agent patterns and routes.
+    pub fn new() -> Self {
+        Self {
+            defaults: true,
+            ..Self::empty()
+        }
+    }
+
+    /// Create a new detector without any rules.
+    pub fn empty() -> Self {
+        Self {
+            user_agent_patterns: Vec::new(),
+            routes: Vec::new(),
+            defaults: false,
+            callback: None,
+            #[cfg(feature = "live-metrics")]
+            exclude_from_live_metrics: false,
+        }
+    }
+
+    /// Add a user agent pattern. Requests whose user agent contains the words of the pattern
+    /// (ignoring case) get the given synthetic source.
+    pub fn with_user_agent_pattern(
+        mut self,
+        pattern: impl AsRef<str>,
+        synthetic_source: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.user_agent_patterns
+            .push((tokenize(pattern.as_ref()), synthetic_source.into()));
+        self
+    }
+
+    /// Add a route. Requests for exactly this route get the given synthetic source.
+    pub fn with_route(
+        mut self,
+        route: impl Into<Cow<'static, str>>,
+        synthetic_source: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.routes.push((route.into(), synthetic_source.into()));
+        self
+    }
+
+    /// Set a callback, which returns the synthetic source of a request. Return `None` to fall
+    /// back to the other rules of this detector.
+    pub fn with_callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&SpanData) -> Option<String> + Send + Sync + 'static,
+    {
+        self.callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Set whether synthetic requests and their dependencies and exceptions are excluded from
+    /// live metrics.
+    ///
+    /// Dependencies and exceptions are only recognized as synthetic if the
+    /// [`OperationContextSpanProcessor`](crate::OperationContextSpanProcessor) is used.
+    ///
+    /// Default: false.
+    #[cfg(feature = "live-metrics")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "live-metrics")))]
+    pub fn with_exclude_from_live_metrics(mut self, exclude_from_live_metrics: bool) -> Self {
+        self.exclude_from_live_metrics = exclude_from_live_metrics;
+        self
+    }
+
+    #[cfg(feature = "live-metrics")]
+    pub(crate) fn exclude_from_live_metrics(&self) -> bool {
+        self.exclude_from_live_metrics
+    }
+
+    /// Synthetic source of the request span, if it is detected as synthetic.
+    pub(crate) fn detect(&self, span: &SpanData) -> Option<String> {
+        if let Some(source) = self.callback.as_ref().and_then(|callback| callback(span)) {
+            return Some(source);
+        }
+
+        let attrs = attrs_to_map(span.attributes.iter());
+        if let Some(user_agent) = attrs.get(semcov::trace::USER_AGENT_ORIGINAL) {
+            let user_agent = tokenize(&user_agent.as_str());
+            let contains = |pattern: &[String]| {
+                !pattern.is_empty()
+                    && user_agent
+                        .windows(pattern.len())
+                        .any(|words| words == pattern)
+            };
+            if let Some((_, source)) = self
+                .user_agent_patterns
+                .iter()
+                .find(|(pattern, _)| contains(pattern))
+            {
+                return Some(source.to_string());
+            }
+            if self.defaults {
+                if let Some((_, source)) = DEFAULT_USER_AGENT_PATTERNS
+                    .iter()
+                    .find(|(pattern, _)| contains(&tokenize(pattern)))
+                {
+                    return Some(source.to_string());
+                }
+            }
+        }
+
+        let route = attrs
+            .get(semcov::trace::HTTP_ROUTE)
+            .or_else(|| attrs.get(semcov::trace::URL_PATH))?
+            .as_str();
+        let defaults = if self.defaults { DEFAULT_ROUTES } else { &[] };
+        self.routes
+            .iter()
+            .map(|(x, source)| (x.as_ref(), source.as_ref()))
+            .chain(defaults.iter().copied())
+            .find(|&(x, _)| x == route.as_ref())
+            .map(|(_, source)| source.to_string())
+    }
+}
+
+/// Synthetic source detector of an exporter, shared by all its clones and the span processors
+/// created from them, so setting the detector applies to all of them.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SharedSyntheticSourceDetector(Arc<RwLock<Option<SyntheticSourceDetector>>>);
+
+impl SharedSyntheticSourceDetector {
+    pub(crate) fn set(&self, detector: SyntheticSourceDetector) {
+        *self.0.write().unwrap() = Some(detector);
+    }
+
+    #[cfg(feature = "live-metrics")]
+    pub(crate) fn exclude_from_live_metrics(&self) -> bool {
+        self.0
+            .read()
+            .unwrap()
+            .as_ref()
+            .is_some_and(SyntheticSourceDetector::exclude_from_live_metrics)
+    }
+
+    /// Synthetic source of the request span, if a detector is set and detects it as synthetic.
+    pub(crate) fn detect(&self, span: &SpanData) -> Option<String> {
+        self.0.read().unwrap().as_ref()?.detect(span)
+    }
+}
+
+/// Lowercase words of a user agent or pattern, split at all characters other than letters and
+/// digits.
+fn tokenize(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::test_span;
+    use opentelemetry::{trace::SpanKind, KeyValue};
+
+    #[test]
+    fn user_agent() {
+        let detector = SyntheticSourceDetector::new().with_user_agent_pattern("Pingdom", "Pingdom");
+        let detect = |user_agent: &'static str| {
+            detector.detect(&test_span(
+                SpanKind::Server,
+                vec![KeyValue::new(
+                    semcov::trace::USER_AGENT_ORIGINAL,
+                    user_agent,
+                )],
+            ))
+        };
+        assert_eq!(detect("kube-probe/1.30"), Some("Kubernetes Probe".into()));
+        assert_eq!(
+            detect("Mozilla/5.0 (compatible; Googlebot/2.1)"),
+            Some("Bot".into())
+        );
+        assert_eq!(
+            detect("Pingdom.com_bot_version_1.4"),
+            Some("Pingdom".into()),
+            "added patterns win over the defaults"
+        );
+        assert_eq!(detect("Mozilla/5.0 (Linux; Android 10; Cubot X30)"), None);
+        assert_eq!(
+            detect("Mozilla/5.0 (compatible; robotics-lab-browser)"),
+            None
+        );
+        assert_eq!(detect("Mozilla/5.0 (X11; Linux x86_64)"), None);
+    }
+
+    #[test]
+    fn route_and_callback() {
+        let detector = SyntheticSourceDetector::empty()
+            .with_route("/status", "Status")
+            .with_callback(|span| {
+                span.attributes
+                    .iter()
+                    .any(|kv| kv.key.as_str() == "test")
+                    .then(|| "Test".to_string())
+            });
+        let route = KeyValue::new(semcov::trace::HTTP_ROUTE, "/status");
+        assert_eq!(
+            detector.detect(&test_span(SpanKind::Server, vec![route.clone()])),
+            Some("Status".into())
+        );
+        assert_eq!(
+            detector.detect(&test_span(
+                SpanKind::Server,
+                vec![route, KeyValue::new("test", true)]
+            )),
+            Some("Test".into())
+        );
+        assert_eq!(
+            detector.detect(&test_span(
+                SpanKind::Server,
+                vec![KeyValue::new(semcov::trace::URL_PATH, "/status")]
+            )),
+            Some("Status".into())
+        );
+        assert_eq!(
+            detector.detect(&test_span(
+                SpanKind::Server,
+                vec![KeyValue::new(semcov::trace::URL_PATH, "/")]
+            )),
+            None
+        );
+
+        let detector = SyntheticSourceDetector::new().with_route("/healthz", "Load Balancer");
+        let detect = |route: &'static str| {
+            detector.detect(&test_span(
+                SpanKind::Server,
+                vec![KeyValue::new(semcov::trace::HTTP_ROUTE, route)],
+            ))
+        };
+        assert_eq!(detect("/healthz"), Some("Load Balancer".into()));
+        assert_eq!(detect("/livez"), Some("Health Check".into()));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
     convert::AttrValue,
     models::context_tag_keys::{self as tags, Tags, TAG_KEY_LOOKUP},
+    resource::AZURE_VM_SCALESET_NAME,
 };
 #[cfg(feature = "trace")]
 use opentelemetry::trace::{SpanId, SpanKind};
@@ -15,6 +16,9 @@ use opentelemetry_sdk::Resource;
 use opentelemetry_semantic_conventions as semcov;
 use std::collections::HashMap;
 
+/// Device type of servers and desktop computers.
+const DEVICE_TYPE_PC: &str = "PC";
+
 #[cfg(feature = "trace")]
 pub(crate) fn get_tags_for_span(span: &SpanData, resource: &Resource) -> Tags {
     let mut tags = Tags::new();
@@ -44,32 +48,51 @@ pub(crate) fn get_tags_for_span(span: &SpanData, resource: &Resource) -> Tags {
         tags.insert(tags::USER_AUTH_USER_ID, user_id.as_str().into_owned());
     }
 
+    if let Some(pseudo_id) = attrs_map.get(semcov::attribute::ENDUSER_PSEUDO_ID) {
+        tags.insert(tags::USER_ID, pseudo_id.as_str().into_owned());
+    }
+
+    if let Some(session_id) = attrs_map.get(semcov::attribute::SESSION_ID) {
+        tags.insert(tags::SESSION_ID, session_id.as_str().into_owned());
+    }
+
+    // The client address of a server span is the address of the end user.
+    if span.span_kind == SpanKind::Server {
+        if let Some(client_address) = attrs_map.get(semcov::trace::CLIENT_ADDRESS).or_else(|| {
+            #[allow(deprecated)]
+            attrs_map.get(semcov::attribute::HTTP_CLIENT_IP)
+        }) {
+            tags.insert(tags::LOCATION_IP, client_address.as_str().into_owned());
+        }
+    }
+
     // Ensure the name of the operation is `METHOD /the/route/path`.
     if span.span_kind == SpanKind::Server || span.span_kind == SpanKind::Consumer {
-        let method = attrs_map
-            .get(semcov::trace::HTTP_REQUEST_METHOD)
-            .or_else(|| {
-                #[allow(deprecated)]
-                attrs_map.get(semcov::attribute::HTTP_METHOD)
-            });
-        let route = attrs_map.get(semcov::trace::HTTP_ROUTE);
-        if let (Some(method), Some(route)) = (method, route) {
-            tags.insert(
-                tags::OPERATION_NAME,
-                format!("{} {}", method.as_str(), route.as_str()),
-            );
+        if let Some(operation_name) = get_http_operation_name(&attrs_map) {
+            tags.insert(tags::OPERATION_NAME, operation_name);
         }
     }
 
     tags
 }
 
+/// Operation name in the format `METHOD /the/route/path`, if the attributes contain both.
 #[cfg(feature = "trace")]
-pub(crate) fn get_tags_for_event(span: &SpanData, resource: &Resource) -> Tags {
-    let mut tags = Tags::new();
-    build_tags_from_resource_attrs(&mut tags, resource, &span.instrumentation_scope);
+pub(crate) fn get_http_operation_name(attrs_map: &HashMap<&str, &dyn AttrValue>) -> Option<String> {
+    let method = attrs_map
+        .get(semcov::trace::HTTP_REQUEST_METHOD)
+        .or_else(|| {
+            #[allow(deprecated)]
+            attrs_map.get(semcov::attribute::HTTP_METHOD)
+        })?;
+    let route = attrs_map.get(semcov::trace::HTTP_ROUTE)?;
+    Some(format!("{} {}", method.as_str(), route.as_str()))
+}
 
-    tags.insert(tags::OPERATION_ID, span.span_context.trace_id().to_string());
+#[cfg(feature = "trace")]
+pub(crate) fn get_tags_for_event(span: &SpanData, resource: &Resource) -> Tags {
+    // Events inherit the context of their span, e.g. the user and the operation name.
+    let mut tags = get_tags_for_span(span, resource);
     tags.insert(
         tags::OPERATION_PARENT_ID,
         span.span_context.span_id().to_string(),
@@ -148,6 +171,20 @@ where
     attrs_map
 }
 
+/// Operating system in the format `<name> <version>`, e.g. `Linux 6.1.0`.
+fn get_os_version(attrs_map: &HashMap<&str, &dyn AttrValue>) -> Option<String> {
+    if let Some(description) = attrs_map.get(semcov::resource::OS_DESCRIPTION) {
+        return Some(description.as_str().into_owned());
+    }
+    let name = attrs_map
+        .get(semcov::resource::OS_NAME)
+        .or_else(|| attrs_map.get(semcov::resource::OS_TYPE))?;
+    Some(match attrs_map.get(semcov::resource::OS_VERSION) {
+        Some(version) => format!("{} {}", name.as_str(), version.as_str()),
+        None => name.as_str().into_owned(),
+    })
+}
+
 fn build_tags_from_resource_attrs(
     tags: &mut Tags,
     resource: &Resource,
@@ -180,6 +217,16 @@ fn build_tags_from_resource_attrs(
                 .or_else(|| attrs_map.get(semcov::resource::K8S_DAEMONSET_NAME))
             {
                 cloud_role = k8s_name.as_str().into_owned();
+            } else if let Some(faas_name) = attrs_map.get(semcov::resource::FAAS_NAME) {
+                cloud_role = faas_name.as_str().into_owned();
+            } else if let Some(scale_set_name) = attrs_map.get(AZURE_VM_SCALESET_NAME) {
+                cloud_role = scale_set_name.as_str().into_owned();
+            } else if let Some(resource_name) = attrs_map
+                .get(semcov::resource::CLOUD_RESOURCE_ID)
+                .and_then(|id| id.as_str().rsplit('/').next().map(str::to_owned))
+                .filter(|name| !name.is_empty())
+            {
+                cloud_role = resource_name;
             }
         }
 
@@ -189,18 +236,38 @@ fn build_tags_from_resource_attrs(
     if let Some(instance) = attrs_map
         .get(semcov::resource::K8S_POD_NAME)
         .or_else(|| attrs_map.get(semcov::resource::SERVICE_INSTANCE_ID))
+        .or_else(|| attrs_map.get(semcov::resource::FAAS_INSTANCE))
+        .or_else(|| attrs_map.get(semcov::resource::HOST_NAME))
     {
         tags.insert(tags::CLOUD_ROLE_INSTANCE, instance.as_str().into_owned());
     }
 
-    if let Some(device_id) = attrs_map.get(semcov::resource::DEVICE_ID) {
+    if let Some(device_id) = attrs_map
+        .get(semcov::resource::DEVICE_ID)
+        .or_else(|| attrs_map.get(semcov::resource::HOST_ID))
+    {
         tags.insert(tags::DEVICE_ID, device_id.as_str().into_owned());
     }
 
-    if let Some(device_model_name) = attrs_map.get(semcov::resource::DEVICE_MODEL_NAME) {
+    if let Some(device_model_name) = attrs_map
+        .get(semcov::resource::DEVICE_MODEL_NAME)
+        .or_else(|| attrs_map.get(semcov::resource::HOST_TYPE))
+        .or_else(|| attrs_map.get(semcov::resource::HOST_ARCH))
+    {
         tags.insert(tags::DEVICE_MODEL, device_model_name.as_str().into_owned());
     }
 
+    if let Some(os_version) = get_os_version(&attrs_map) {
+        tags.insert(tags::DEVICE_OS_VERSION, os_version);
+    }
+
+    // Host, OS and process attributes describe a server, which Application Insights calls a PC.
+    if attrs_map.keys().any(|k| {
+        k.starts_with("host.") || k.starts_with("os.") || k.starts_with("process.runtime.")
+    }) {
+        tags.insert(tags::DEVICE_TYPE, DEVICE_TYPE_PC.into());
+    }
+
     if let Some(service_version) = attrs_map.get(semcov::resource::SERVICE_VERSION) {
         tags.insert(
             tags::APPLICATION_VERSION,
@@ -219,3 +286,56 @@ fn build_tags_from_resource_attrs(
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::KeyValue;
+
+    fn resource_tags(attributes: Vec<KeyValue>) -> Tags {
+        let mut tags = Tags::new();
+        let resource = Resource::builder_empty()
+            .with_attributes(attributes)
+            .build();
+        build_tags_from_resource_attrs(&mut tags, &resource, &Default::default());
+        tags
+    }
+
+    #[test]
+    fn host_and_os() {
+        let tags = resource_tags(vec![
+            KeyValue::new(semcov::resource::HOST_NAME, "web-1"),
+            KeyValue::new(semcov::resource::HOST_ARCH, "amd64"),
+            KeyValue::new(semcov::resource::OS_TYPE, "linux"),
+            KeyValue::new(semcov::resource::OS_VERSION, "6.1.0"),
+        ]);
+        assert_eq!(tags.get(&tags::CLOUD_ROLE_INSTANCE).unwrap(), "web-1");
+        assert_eq!(tags.get(&tags::DEVICE_MODEL).unwrap(), "amd64");
+        assert_eq!(tags.get(&tags::DEVICE_OS_VERSION).unwrap(), "linux 6.1.0");
+        assert_eq!(tags.get(&tags::DEVICE_TYPE).unwrap(), "PC");
+    }
+
+    #[test]
+    fn precedence() {
+        let tags = resource_tags(vec![
+            KeyValue::new(semcov::resource::HOST_NAME, "web-1"),
+            KeyValue::new(semcov::resource::SERVICE_INSTANCE_ID, "instance"),
+            KeyValue::new(semcov::resource::HOST_ARCH, "amd64"),
+            KeyValue::new(semcov::resource::HOST_TYPE, "Standard_D2s_v3"),
+            KeyValue::new(semcov::resource::OS_TYPE, "linux"),
+            KeyValue::new(semcov::resource::OS_DESCRIPTION, "Ubuntu 22.04"),
+        ]);
+        assert_eq!(tags.get(&tags::CLOUD_ROLE_INSTANCE).unwrap(), "instance");
+        assert_eq!(tags.get(&tags::DEVICE_MODEL).unwrap(), "Standard_D2s_v3");
+        assert_eq!(tags.get(&tags::DEVICE_OS_VERSION).unwrap(), "Ubuntu 22.04");
+    }
+
+    #[test]
+    fn no_device_type_without_host() {
+        let tags = resource_tags(vec![KeyValue::new(
+            semcov::resource::SERVICE_NAME,
+            "my-service",
+        )]);
+        assert!(tags.get(&tags::DEVICE_TYPE).is_none());
+    }
+}
@@ -3,16 +3,23 @@ use crate::{
         attrs_map_to_properties, attrs_to_map, attrs_to_properties, duration_to_string,
         status_to_result_code, time_to_string, value_to_severity_level,
     },
+    exception::{attrs_to_escaped_severity_level, attrs_to_exception_details},
     models::{
-        context_tag_keys::attrs::CUSTOM_EVENT_NAME, Data, Envelope, EventData, ExceptionData,
-        ExceptionDetails, LimitedLenString, MessageData, RemoteDependencyData, RequestData,
+        context_tag_keys::{
+            self,
+            attrs::{CUSTOM_EVENT_NAME, PAGE_VIEW},
+            Tags,
+        },
+        Data, Envelope, EventData, ExceptionData, LimitedLenString, Measurements, MessageData,
+        PageViewData, Properties, RemoteDependencyData, RequestData,
     },
     tags::{get_tags_for_event, get_tags_for_span},
-    Exporter,
+    Exporter, SuccessPolicy,
 };
+use chrono::DateTime;
 use opentelemetry::{
     trace::{Event, SpanKind, Status},
-    Value,
+    Array, Value,
 };
 use opentelemetry_http::HttpClient;
 use opentelemetry_sdk::{
@@ -21,7 +28,13 @@ use opentelemetry_sdk::{
     Resource,
 };
 use opentelemetry_semantic_conventions as semcov;
-use std::{borrow::Cow, collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    convert::TryFrom,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 /// Deprecated semantic convention key for HTTP host
 ///
@@ -31,6 +44,9 @@ const DEPRECATED_HTTP_HOST: &str = "http.host";
 /// Semantic convention key for HTTP 'Host' request header.
 const HTTP_REQUEST_HEADER_HOST: &str = "http.request.header.host";
 
+/// Semantic convention key for HTTP 'Referer' request header.
+const HTTP_REQUEST_HEADER_REFERER: &str = "http.request.header.referer";
+
 /// Deprecated semantic convention key for peer IP.
 ///
 /// Replaced in https://github.com/open-telemetry/opentelemetry-specification/pull/2614 with
@@ -59,7 +75,61 @@ const DEPRECATED_SERVER_SOCKET_ADDRESS: &str = "server.socket.address";
 ///
 /// Replaced in https://github.com/open-telemetry/opentelemetry-specification/pull/3713 with
 /// `network.local.port`.
-const DEPRECATED_SERVER_SOCKET_PORT: &str = "server.socket.port";
+pub(crate) const DEPRECATED_SERVER_SOCKET_PORT: &str = "server.socket.port";
+
+/// Attribute containing the time a message was enqueued, either as Unix epoch milliseconds or as
+/// RFC 3339 string.
+///
+/// Not part of the semantic conventions (yet), but used to calculate the time since enqueued for
+/// consumer spans.
+pub(crate) const MESSAGING_MESSAGE_ENQUEUED_TIME: &str = "messaging.message.enqueued_time";
+
+/// Link attribute used by the Azure SDKs for the time a message was enqueued as Unix epoch
+/// milliseconds.
+const AZURE_SDK_ENQUEUED_TIME: &str = "enqueuedTime";
+
+/// Measurement name used by the Azure Monitor exporters for the time between enqueueing and
+/// processing a message.
+const MEASUREMENT_TIME_SINCE_ENQUEUED: &str = "timeSinceEnqueued";
+
+/// Attribute used by the Azure SDKs for the resource provider namespace of the called service, e.g.
+/// `Microsoft.ServiceBus`.
+///
+/// See https://github.com/Azure/azure-sdk/blob/main/docs/tracing/distributed-tracing-conventions.md
+const AZ_NAMESPACE: &str = "az.namespace";
+
+/// Attribute used by older Azure SDKs for the messaging broker address.
+const AZURE_SDK_PEER_ADDRESS: &str = "peer.address";
+
+/// Attribute used by older Azure SDKs for the messaging entity name.
+const AZURE_SDK_MESSAGE_BUS_DESTINATION: &str = "message_bus.destination";
+
+/// Semantic convention key for the GenAI provider.
+///
+/// Replaces `gen_ai.system` in newer versions of the semantic conventions.
+const GEN_AI_PROVIDER_NAME: &str = "gen_ai.provider.name";
+
+/// gRPC status codes, which indicate an error on the server side according to the semantic
+/// conventions. On the client side all status codes other than `OK` (0) indicate an error.
+const GRPC_SERVER_ERROR_STATUS_CODES: [i64; 6] = [
+    2,  // UNKNOWN
+    4,  // DEADLINE_EXCEEDED
+    12, // UNIMPLEMENTED
+    13, // INTERNAL
+    14, // UNAVAILABLE
+    15, // DATA_LOSS
+];
+
+/// Connect RPC error codes, which indicate an error on the server side according to the semantic
+/// conventions. On the client side all error codes indicate an error.
+const CONNECT_RPC_SERVER_ERROR_CODES: [&str; 6] = [
+    "unknown",
+    "deadline_exceeded",
+    "unimplemented",
+    "internal",
+    "unavailable",
+    "data_loss",
+];
 
 pub(crate) const EVENT_NAME_CUSTOM: &str = "ai.custom";
 pub(crate) const EVENT_NAME_EXCEPTION: &str = "exception";
@@ -68,10 +138,22 @@ impl<C> Exporter<C> {
     fn create_envelopes_for_span(&self, span: SpanData, resource: &Resource) -> Vec<Envelope> {
         let mut result = Vec::with_capacity(1 + span.events.len());
 
-        let (data, tags, name) = match span.span_kind {
+        let (mut data, tags, name) = match span.span_kind {
+            _ if is_page_view(&span) => {
+                let data: PageViewData = (&span, resource).into();
+                let mut tags = get_tags_for_span(&span, resource);
+                self.apply_operation_context(&span, &mut tags);
+                (
+                    Data::PageView(data),
+                    tags,
+                    "Microsoft.ApplicationInsights.PageView",
+                )
+            }
             SpanKind::Server | SpanKind::Consumer => {
-                let data: RequestData = SpanAndResource(&span, resource).into();
-                let tags = get_tags_for_span(&span, resource);
+                let data: RequestData =
+                    SpanAndResource(&span, resource, &self.success_policy.get()).into();
+                let mut tags = get_tags_for_span(&span, resource);
+                self.apply_operation_context(&span, &mut tags);
                 (
                     Data::Request(data),
                     tags,
@@ -79,8 +161,10 @@ impl<C> Exporter<C> {
                 )
             }
             SpanKind::Client | SpanKind::Producer | SpanKind::Internal => {
-                let data: RemoteDependencyData = SpanAndResource(&span, resource).into();
-                let tags = get_tags_for_span(&span, resource);
+                let data: RemoteDependencyData =
+                    SpanAndResource(&span, resource, &self.success_policy.get()).into();
+                let mut tags = get_tags_for_span(&span, resource);
+                self.apply_operation_context(&span, &mut tags);
                 (
                     Data::RemoteDependency(data),
                     tags,
@@ -88,6 +172,18 @@ impl<C> Exporter<C> {
                 )
             }
         };
+        if let Some((properties, measurements)) = data.properties_and_measurements_mut() {
+            self.measurements_policy.apply(
+                &attrs_to_map(span.attributes.iter()),
+                properties,
+                measurements,
+            );
+        }
+        #[cfg(feature = "metrics")]
+        crate::standard_metrics::mark_processed_by_metric_extractors(
+            &self.standard_metrics,
+            &mut data,
+        );
         result.push(Envelope {
             name,
             time: time_to_string(span.start_time).into(),
@@ -103,7 +199,7 @@ impl<C> Exporter<C> {
             None
         };
         for event in span.events.iter() {
-            let (data, name) = match event.name.as_ref() {
+            let (mut data, name) = match event.name.as_ref() {
                 x if x == EVENT_NAME_CUSTOM => (
                     Data::Event(EventAndResource(event, event_resource).into()),
                     "Microsoft.ApplicationInsights.Event",
@@ -117,18 +213,69 @@ impl<C> Exporter<C> {
                     "Microsoft.ApplicationInsights.Message",
                 ),
             };
+            self.add_span_attributes_to_event(&span, &mut data);
+            let mut event_tags = get_tags_for_event(&span, resource);
+            self.apply_operation_context(&span, &mut event_tags);
+            if let Some((properties, measurements)) = data.properties_and_measurements_mut() {
+                self.measurements_policy.apply(
+                    &attrs_to_map(event.attributes.iter()),
+                    properties,
+                    measurements,
+                );
+            }
             result.push(Envelope {
                 name,
                 time: time_to_string(event.timestamp).into(),
                 sample_rate: Some(self.sample_rate),
                 i_key: Some(self.instrumentation_key.clone().into()),
-                tags: Some(get_tags_for_event(&span, resource)),
+                tags: Some(event_tags),
                 data: Some(data),
             });
         }
 
         result
     }
+
+    /// Add the context of the operation to the tags, e.g. the operation name and the synthetic
+    /// source.
+    fn apply_operation_context(&self, span: &SpanData, tags: &mut Tags) {
+        if matches!(span.span_kind, SpanKind::Server | SpanKind::Consumer)
+            && !tags.contains_key(&context_tag_keys::OPERATION_SYNTHETIC_SOURCE)
+        {
+            if let Some(synthetic_source) = self.synthetic_source_detector.detect(span) {
+                tags.insert(
+                    context_tag_keys::OPERATION_SYNTHETIC_SOURCE,
+                    synthetic_source,
+                );
+            }
+        }
+        self.operation_contexts
+            .apply_to_tags(span.span_context.trace_id(), tags);
+    }
+
+    fn add_span_attributes_to_event(&self, span: &SpanData, data: &mut Data) {
+        if self.span_attributes_in_events.is_empty() {
+            return;
+        }
+        let properties = match data {
+            Data::Event(data) => &mut data.properties,
+            Data::Exception(data) => &mut data.properties,
+            Data::Message(data) => &mut data.properties,
+            _ => return,
+        };
+        for kv in span.attributes.iter() {
+            if self
+                .span_attributes_in_events
+                .iter()
+                .any(|key| key == kv.key.as_str())
+            {
+                properties
+                    .get_or_insert_with(Properties::new)
+                    .entry(kv.key.as_str().into())
+                    .or_insert_with(|| kv.value.as_str().into());
+            }
+        }
+    }
 }
 
 #[cfg_attr(docsrs, doc(cfg(feature = "trace")))]
@@ -144,6 +291,10 @@ where
             .into_iter()
             .flat_map(|span| self.create_envelopes_for_span(span, &self.resource))
             .collect();
+        let envelopes = self.deduplicate(envelopes);
+        if envelopes.is_empty() {
+            return Ok(());
+        }
 
         crate::uploader::send(
             client.as_ref(),
@@ -155,6 +306,10 @@ where
         .map_err(Into::into)
     }
 
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.flush_deduplication(timeout)
+    }
+
     fn set_resource(&mut self, resource: &Resource) {
         self.resource = resource.clone();
     }
@@ -202,36 +357,432 @@ fn get_server_host<'v>(attrs: &HashMap<&str, &'v Value>) -> Option<Cow<'v, str>>
     }
 }
 
+fn get_messaging_operation<'v>(attrs: &HashMap<&str, &'v Value>) -> Option<&'v Value> {
+    attrs
+        .get(semcov::attribute::MESSAGING_OPERATION_NAME)
+        .or_else(|| {
+            attrs.get(
+                #[allow(deprecated)]
+                semcov::attribute::MESSAGING_OPERATION,
+            )
+        })
+        .or_else(|| attrs.get(semcov::attribute::MESSAGING_OPERATION_TYPE))
+        .copied()
+}
+
+fn get_messaging_target<'v>(attrs: &HashMap<&str, &'v Value>) -> Option<Cow<'v, str>> {
+    let destination = attrs.get(semcov::attribute::MESSAGING_DESTINATION_NAME)?;
+    if let Some(host) = attrs.get(semcov::trace::SERVER_ADDRESS) {
+        Some(format!("{}/{}", host.as_str(), destination.as_str()).into())
+    } else {
+        Some(destination.as_str())
+    }
+}
+
+/// Target of a call made by an Azure SDK in the format `<host>/<entity>`, e.g.
+/// `my-namespace.servicebus.windows.net/my-queue`.
+fn get_azure_sdk_target(attrs: &HashMap<&str, &Value>) -> Option<String> {
+    let host = attrs
+        .get(semcov::trace::SERVER_ADDRESS)
+        .or_else(|| {
+            attrs.get(
+                #[allow(deprecated)]
+                semcov::attribute::NET_PEER_NAME,
+            )
+        })
+        .or_else(|| attrs.get(AZURE_SDK_PEER_ADDRESS))?
+        .as_str();
+    let host = host.split("://").last().unwrap_or_default();
+    let host = host.trim_end_matches('/');
+    if let Some(entity) = attrs
+        .get(semcov::attribute::MESSAGING_DESTINATION_NAME)
+        .or_else(|| attrs.get(AZURE_SDK_MESSAGE_BUS_DESTINATION))
+    {
+        Some(format!("{}/{}", host, entity.as_str()))
+    } else {
+        Some(host.into())
+    }
+}
+
+/// Dependency type for a call made by an Azure SDK, matching the types used by the official
+/// Azure Monitor exporters.
+fn get_azure_sdk_dependency_type(namespace: &str, span_kind: &SpanKind, target: &str) -> String {
+    let service = match namespace {
+        "Microsoft.EventHub" => "Azure Event Hubs",
+        "Microsoft.ServiceBus" => "Azure Service Bus",
+        "Microsoft.Storage" if target.contains(".blob.") => "Azure blob",
+        "Microsoft.Storage" if target.contains(".queue.") => "Azure queue",
+        "Microsoft.Storage" if target.contains(".table.") => "Azure table",
+        "Microsoft.Storage" if target.contains(".file.") => "Azure file",
+        "Microsoft.KeyVault" => "Azure Key Vault",
+        "Microsoft.DocumentDB" => "Azure DocumentDB",
+        "Microsoft.AppConfiguration" => "Azure App Configuration",
+        other => other,
+    };
+    match span_kind {
+        SpanKind::Internal => format!("InProc | {}", namespace),
+        SpanKind::Producer => format!("Queue Message | {}", service),
+        _ => service.into(),
+    }
+}
+
+fn value_to_time(value: &Value) -> Option<SystemTime> {
+    match value {
+        Value::I64(millis) => {
+            Some(SystemTime::UNIX_EPOCH + Duration::from_millis(u64::try_from(*millis).ok()?))
+        }
+        Value::F64(millis) if *millis >= 0.0 => {
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs_f64(*millis / 1000.0))
+        }
+        Value::String(s) => DateTime::parse_from_rfc3339(s.as_str())
+            .ok()
+            .map(SystemTime::from),
+        _ => None,
+    }
+}
+
+/// Time in milliseconds between enqueueing a message and starting to process it. Uses the
+/// enqueued time on the span if available. Otherwise uses the average over all links (used by the
+/// Azure SDKs for batch receives).
+fn get_time_since_enqueued(span: &SpanData, attrs: &HashMap<&str, &Value>) -> Option<f64> {
+    let since = |enqueued_time: SystemTime| {
+        span.start_time
+            .duration_since(enqueued_time)
+            .unwrap_or_default()
+            .as_secs_f64()
+            * 1000.0
+    };
+
+    if let Some(enqueued_time) = attrs
+        .get(MESSAGING_MESSAGE_ENQUEUED_TIME)
+        .and_then(|&v| value_to_time(v))
+    {
+        return Some(since(enqueued_time));
+    }
+
+    let link_times: Vec<f64> = span
+        .links
+        .iter()
+        .filter_map(|link| {
+            link.attributes
+                .iter()
+                .find(|kv| kv.key.as_str() == AZURE_SDK_ENQUEUED_TIME)
+                .and_then(|kv| value_to_time(&kv.value))
+        })
+        .map(since)
+        .collect();
+    if link_times.is_empty() {
+        None
+    } else {
+        Some(link_times.iter().sum::<f64>() / link_times.len() as f64)
+    }
+}
+
+fn get_rpc_status_code<'v>(attrs: &HashMap<&str, &'v Value>) -> Option<&'v Value> {
+    attrs
+        .get(semcov::attribute::RPC_GRPC_STATUS_CODE)
+        .or_else(|| attrs.get(semcov::attribute::RPC_CONNECT_RPC_ERROR_CODE))
+        .or_else(|| attrs.get(semcov::attribute::RPC_JSONRPC_ERROR_CODE))
+        .copied()
+}
+
+fn get_rpc_name(attrs: &HashMap<&str, &Value>) -> Option<String> {
+    if let (Some(service), Some(method)) = (
+        attrs.get(semcov::attribute::RPC_SERVICE),
+        attrs.get(semcov::attribute::RPC_METHOD),
+    ) {
+        Some(format!("{}/{}", service.as_str(), method.as_str()))
+    } else {
+        None
+    }
+}
+
+/// Success of an RPC span based on the status attributes defined in the semantic conventions, if
+/// any.
+fn get_rpc_success(span: &SpanData) -> Option<bool> {
+    let is_server = matches!(span.span_kind, SpanKind::Server | SpanKind::Consumer);
+    span.attributes.iter().find_map(|kv| match kv.key.as_str() {
+        k if k == semcov::attribute::RPC_GRPC_STATUS_CODE => {
+            let code = match &kv.value {
+                Value::I64(code) => *code,
+                Value::String(code) => code.as_str().parse().ok()?,
+                _ => return None,
+            };
+            Some(if is_server {
+                !GRPC_SERVER_ERROR_STATUS_CODES.contains(&code)
+            } else {
+                code == 0
+            })
+        }
+        k if k == semcov::attribute::RPC_CONNECT_RPC_ERROR_CODE => {
+            Some(is_server && !CONNECT_RPC_SERVER_ERROR_CODES.contains(&kv.value.as_str().as_ref()))
+        }
+        k if k == semcov::attribute::RPC_JSONRPC_ERROR_CODE => Some(false),
+        _ => None,
+    })
+}
+
 pub(crate) fn get_duration(span: &SpanData) -> Duration {
     span.end_time
         .duration_since(span.start_time)
         .unwrap_or_default()
 }
 
-pub(crate) fn is_request_success(span: &SpanData) -> bool {
-    !matches!(span.status, Status::Error { .. })
+pub(crate) fn is_request_success(span: &SpanData, success_policy: &SuccessPolicy) -> bool {
+    if let Some(success) = success_policy.evaluate(span, true) {
+        return success;
+    }
+    match span.status {
+        Status::Unset => get_rpc_success(span).unwrap_or(true),
+        Status::Ok => true,
+        Status::Error { .. } => false,
+    }
 }
 
-pub(crate) fn is_remote_dependency_success(span: &SpanData) -> Option<bool> {
+pub(crate) fn is_remote_dependency_success(
+    span: &SpanData,
+    success_policy: &SuccessPolicy,
+) -> Option<bool> {
+    if let Some(success) = success_policy.evaluate(span, false) {
+        return Some(success);
+    }
     match span.status {
-        Status::Unset => None,
+        Status::Unset => get_rpc_success(span),
         Status::Ok => Some(true),
         Status::Error { .. } => Some(false),
     }
 }
 
-struct SpanAndResource<'a>(&'a SpanData, &'a Resource);
+/// Result code of a request or dependency: the HTTP or RPC status code, otherwise a code for the
+/// span status.
+pub(crate) fn get_result_code<'v>(
+    span: &SpanData,
+    attrs: &HashMap<&str, &'v Value>,
+) -> Cow<'v, str> {
+    attrs
+        .get(semcov::trace::HTTP_RESPONSE_STATUS_CODE)
+        .or_else(|| {
+            attrs.get(
+                #[allow(deprecated)]
+                semcov::attribute::HTTP_STATUS_CODE,
+            )
+        })
+        .copied()
+        .or_else(|| get_rpc_status_code(attrs))
+        .map(|status_code| status_code.as_str())
+        .unwrap_or_else(|| status_to_result_code(&span.status).to_string().into())
+}
+
+/// Target of a dependency, e.g. the host and port of the server, the database or the queue.
+pub(crate) fn get_dependency_target<'v>(
+    span: &SpanData,
+    attrs: &HashMap<&str, &'v Value>,
+) -> Option<Cow<'v, str>> {
+    if attrs.contains_key(AZ_NAMESPACE)
+        && matches!(span.span_kind, SpanKind::Client | SpanKind::Producer)
+    {
+        if let Some(target) = get_azure_sdk_target(attrs) {
+            return Some(target.into());
+        }
+    }
+    if let Some(target) = get_messaging_target(attrs) {
+        return Some(target);
+    }
+
+    if let Some(host) = attrs
+        .get(HTTP_REQUEST_HEADER_HOST)
+        .or_else(|| attrs.get(DEPRECATED_HTTP_HOST))
+    {
+        return Some(host.as_str());
+    }
+    if let Some(peer_name) = attrs
+        .get(semcov::trace::SERVER_ADDRESS)
+        .or_else(|| attrs.get(semcov::trace::NETWORK_PEER_ADDRESS))
+        .or_else(|| attrs.get(DEPRECATED_SERVER_SOCKET_ADDRESS))
+        .or_else(|| {
+            attrs.get(
+                #[allow(deprecated)]
+                semcov::attribute::NET_SOCK_PEER_NAME,
+            )
+        })
+        .or_else(|| {
+            attrs.get(
+                #[allow(deprecated)]
+                semcov::attribute::NET_PEER_NAME,
+            )
+        })
+        .or_else(|| {
+            attrs.get(
+                #[allow(deprecated)]
+                semcov::attribute::NET_SOCK_PEER_ADDR,
+            )
+        })
+        .or_else(|| attrs.get(DEPRECATED_NET_PEER_IP))
+    {
+        let peer_port = attrs
+            .get(semcov::trace::SERVER_PORT)
+            .or_else(|| attrs.get(semcov::trace::NETWORK_PEER_PORT))
+            .or_else(|| attrs.get(DEPRECATED_SERVER_SOCKET_PORT))
+            .or_else(|| {
+                attrs.get(
+                    #[allow(deprecated)]
+                    semcov::attribute::NET_SOCK_PEER_PORT,
+                )
+            })
+            .or_else(|| {
+                attrs.get(
+                    #[allow(deprecated)]
+                    semcov::attribute::NET_PEER_PORT,
+                )
+            });
+        return Some(match peer_port {
+            Some(peer_port) => format!("{}:{}", peer_name.as_str(), peer_port.as_str()).into(),
+            None => peer_name.as_str(),
+        });
+    }
+    attrs
+        .get(semcov::attribute::DB_NAMESPACE)
+        .or_else(|| {
+            attrs.get(
+                #[allow(deprecated)]
+                semcov::attribute::DB_NAME,
+            )
+        })
+        .or_else(|| attrs.get(semcov::attribute::RPC_SERVICE))
+        .map(|value| value.as_str())
+}
+
+/// Type of a dependency, e.g. `HTTP`, the database system or the Azure service.
+pub(crate) fn get_dependency_type<'v>(
+    span: &SpanData,
+    attrs: &HashMap<&str, &'v Value>,
+) -> Option<Cow<'v, str>> {
+    if let Some(namespace) = attrs.get(AZ_NAMESPACE) {
+        let target = get_azure_sdk_target(attrs);
+        return Some(
+            get_azure_sdk_dependency_type(
+                &namespace.as_str(),
+                &span.span_kind,
+                target.as_deref().unwrap_or_default(),
+            )
+            .into(),
+        );
+    }
+
+    if span.span_kind == SpanKind::Internal {
+        Some("InProc".into())
+    } else if let Some(gen_ai_provider) = attrs.get(GEN_AI_PROVIDER_NAME).or_else(|| {
+        attrs.get(
+            #[allow(deprecated)]
+            semcov::attribute::GEN_AI_SYSTEM,
+        )
+    }) {
+        Some(gen_ai_provider.as_str())
+    } else if let Some(db_system) = attrs.get(semcov::trace::DB_SYSTEM_NAME).or_else(|| {
+        attrs.get(
+            #[allow(deprecated)]
+            semcov::attribute::DB_SYSTEM,
+        )
+    }) {
+        Some(db_system.as_str())
+    } else if let Some(messaging_system) = attrs.get(semcov::attribute::MESSAGING_SYSTEM) {
+        Some(format!("Queue Message | {}", messaging_system.as_str()).into())
+    } else if let Some(rpc_system) = attrs.get(semcov::trace::RPC_SYSTEM) {
+        Some(if rpc_system.as_str() == "grpc" {
+            "GRPC".into()
+        } else {
+            rpc_system.as_str()
+        })
+    } else if attrs.keys().any(|key| key.starts_with("http.")) {
+        Some("HTTP".into())
+    } else if attrs.keys().any(|key| key.starts_with("db.")) {
+        Some("DB".into())
+    } else {
+        None
+    }
+}
+
+pub(crate) fn is_page_view(span: &SpanData) -> bool {
+    span.attributes
+        .iter()
+        .any(|kv| kv.key.as_str() == PAGE_VIEW && kv.value == Value::Bool(true))
+}
+
+/// Value of an HTTP header attribute, which the semantic conventions define as a string array.
+fn get_header_value(value: &Value) -> Cow<'_, str> {
+    match value {
+        Value::Array(Array::String(values)) => values
+            .first()
+            .map(|x| x.as_str().into())
+            .unwrap_or_default(),
+        value => value.as_str(),
+    }
+}
+
+pub(crate) struct SpanAndResource<'a>(
+    pub(crate) &'a SpanData,
+    pub(crate) &'a Resource,
+    pub(crate) &'a SuccessPolicy,
+);
+
+impl<'a> From<(&'a SpanData, &'a Resource)> for PageViewData {
+    fn from((span, resource): (&'a SpanData, &'a Resource)) -> PageViewData {
+        let mut data = PageViewData {
+            ver: 2,
+            name: span.name.clone().into(),
+            url: None,
+            duration: Some(duration_to_string(get_duration(span))),
+            referrer_uri: None,
+            id: Some(span.span_context.span_id().to_string().into()),
+            properties: attrs_to_properties(
+                span.attributes.iter(),
+                Some(resource),
+                &span.links.links,
+            ),
+            measurements: None,
+        };
+
+        let attrs: HashMap<&str, &Value> = span
+            .attributes
+            .iter()
+            .map(|kv| (kv.key.as_str(), &kv.value))
+            .collect();
+        if let Some(&url) = attrs.get(semcov::trace::URL_FULL).or_else(|| {
+            attrs.get(
+                #[allow(deprecated)]
+                semcov::attribute::HTTP_URL,
+            )
+        }) {
+            data.url = Some(url.into());
+        }
+        if let Some(&referrer) = attrs.get(HTTP_REQUEST_HEADER_REFERER) {
+            data.referrer_uri = Some(get_header_value(referrer).into());
+        }
+        if let Some(properties) = data.properties.as_mut() {
+            properties.remove(&PAGE_VIEW.into());
+        }
+        data.properties = data.properties.filter(|x| !x.is_empty());
+
+        data
+    }
+}
 
 impl<'a> From<SpanAndResource<'a>> for RequestData {
-    fn from(SpanAndResource(span, resource): SpanAndResource<'a>) -> RequestData {
+    fn from(SpanAndResource(span, resource, success_policy): SpanAndResource<'a>) -> RequestData {
+        let attrs: HashMap<&str, &Value> = span
+            .attributes
+            .iter()
+            .map(|kv| (kv.key.as_str(), &kv.value))
+            .collect();
+
         let mut data = RequestData {
             ver: 2,
             id: span.span_context.span_id().to_string().into(),
             name: Some(LimitedLenString::<1024>::from(span.name.clone()))
                 .filter(|x| !x.as_ref().is_empty()),
             duration: duration_to_string(get_duration(span)),
-            response_code: status_to_result_code(&span.status).to_string().into(),
-            success: is_request_success(span),
+            response_code: get_result_code(span, &attrs).into(),
+            success: is_request_success(span, success_policy),
             source: None,
             url: None,
             properties: attrs_to_properties(
@@ -239,14 +790,9 @@ impl<'a> From<SpanAndResource<'a>> for RequestData {
                 Some(resource),
                 &span.links.links,
             ),
+            measurements: None,
         };
 
-        let attrs: HashMap<&str, &Value> = span
-            .attributes
-            .iter()
-            .map(|kv| (kv.key.as_str(), &kv.value))
-            .collect();
-
         if let Some(&method) = attrs.get(semcov::trace::HTTP_REQUEST_METHOD).or_else(|| {
             #[allow(deprecated)]
             attrs.get(semcov::attribute::HTTP_METHOD)
@@ -256,15 +802,8 @@ impl<'a> From<SpanAndResource<'a>> for RequestData {
             } else {
                 method.into()
             });
-        }
-
-        if let Some(&status_code) = attrs.get(semcov::trace::HTTP_RESPONSE_STATUS_CODE) {
-            data.response_code = status_code.into();
-        } else if let Some(&status_code) = attrs.get(
-            #[allow(deprecated)]
-            semcov::attribute::HTTP_STATUS_CODE,
-        ) {
-            data.response_code = status_code.into();
+        } else if let Some(rpc_name) = get_rpc_name(&attrs) {
+            data.name = Some(rpc_name.into());
         }
 
         if let Some(&url) = attrs.get(semcov::trace::URL_FULL) {
@@ -312,42 +851,64 @@ impl<'a> From<SpanAndResource<'a>> for RequestData {
             data.source = Some(peer_ip.into());
         }
 
+        if span.span_kind == SpanKind::Consumer {
+            if let Some(source) = get_messaging_target(&attrs) {
+                data.source = Some(source.into());
+            }
+            if attrs.contains_key(AZ_NAMESPACE) {
+                if let Some(source) = get_azure_sdk_target(&attrs) {
+                    data.source = Some(source.into());
+                }
+            }
+
+            if let Some(time_since_enqueued) = get_time_since_enqueued(span, &attrs) {
+                let mut measurements = Measurements::new();
+                measurements.insert(MEASUREMENT_TIME_SINCE_ENQUEUED.into(), time_since_enqueued);
+                data.measurements = Some(measurements);
+            }
+        }
+
         data
     }
 }
 
 impl<'a> From<SpanAndResource<'a>> for RemoteDependencyData {
-    fn from(SpanAndResource(span, resource): SpanAndResource<'a>) -> RemoteDependencyData {
+    fn from(
+        SpanAndResource(span, resource, success_policy): SpanAndResource<'a>,
+    ) -> RemoteDependencyData {
+        let attrs: HashMap<&str, &Value> = span
+            .attributes
+            .iter()
+            .map(|kv| (kv.key.as_str(), &kv.value))
+            .collect();
+
         let mut data = RemoteDependencyData {
             ver: 2,
             id: Some(span.span_context.span_id().to_string().into()),
             name: span.name.clone().into(),
             duration: duration_to_string(get_duration(span)),
-            result_code: Some(status_to_result_code(&span.status).to_string().into()),
-            success: is_remote_dependency_success(span),
+            result_code: Some(get_result_code(span, &attrs).into()),
+            success: is_remote_dependency_success(span, success_policy),
             data: None,
-            target: None,
-            type_: None,
+            target: get_dependency_target(span, &attrs).map(Into::into),
+            type_: get_dependency_type(span, &attrs).map(Into::into),
             properties: attrs_to_properties(
                 span.attributes.iter(),
                 Some(resource),
                 &span.links.links,
             ),
+            measurements: None,
         };
 
-        let attrs: HashMap<&str, &Value> = span
-            .attributes
-            .iter()
-            .map(|kv| (kv.key.as_str(), &kv.value))
-            .collect();
+        if let Some(rpc_name) = get_rpc_name(&attrs) {
+            data.name = rpc_name.into();
+        }
 
-        if let Some(&status_code) = attrs.get(semcov::trace::HTTP_RESPONSE_STATUS_CODE) {
-            data.result_code = Some(status_code.into());
-        } else if let Some(&status_code) = attrs.get(
-            #[allow(deprecated)]
-            semcov::attribute::HTTP_STATUS_CODE,
+        if let (Some(operation), Some(model)) = (
+            attrs.get(semcov::attribute::GEN_AI_OPERATION_NAME),
+            attrs.get(semcov::attribute::GEN_AI_REQUEST_MODEL),
         ) {
-            data.result_code = Some(status_code.into());
+            data.name = format!("{} {}", operation.as_str(), model.as_str()).into();
         }
 
         if let Some(&url) = attrs.get(semcov::trace::URL_FULL) {
@@ -366,83 +927,11 @@ impl<'a> From<SpanAndResource<'a>> for RemoteDependencyData {
             data.data = Some(statement.into());
         }
 
-        if let Some(&host) = attrs.get(HTTP_REQUEST_HEADER_HOST) {
-            data.target = Some(host.into());
-        } else if let Some(&host) = attrs.get(DEPRECATED_HTTP_HOST) {
-            data.target = Some(host.into());
-        } else if let Some(&peer_name) = attrs
-            .get(semcov::trace::SERVER_ADDRESS)
-            .or_else(|| attrs.get(semcov::trace::NETWORK_PEER_ADDRESS))
-            .or_else(|| attrs.get(DEPRECATED_SERVER_SOCKET_ADDRESS))
-            .or_else(|| {
-                attrs.get(
-                    #[allow(deprecated)]
-                    semcov::attribute::NET_SOCK_PEER_NAME,
-                )
-            })
-            .or_else(|| {
-                attrs.get(
-                    #[allow(deprecated)]
-                    semcov::attribute::NET_PEER_NAME,
-                )
-            })
-            .or_else(|| {
-                attrs.get(
-                    #[allow(deprecated)]
-                    semcov::attribute::NET_SOCK_PEER_ADDR,
-                )
-            })
-            .or_else(|| attrs.get(DEPRECATED_NET_PEER_IP))
-        {
-            if let Some(peer_port) = attrs
-                .get(semcov::trace::SERVER_PORT)
-                .or_else(|| attrs.get(semcov::trace::NETWORK_PEER_PORT))
-                .or_else(|| attrs.get(DEPRECATED_SERVER_SOCKET_PORT))
-                .or_else(|| {
-                    attrs.get(
-                        #[allow(deprecated)]
-                        semcov::attribute::NET_SOCK_PEER_PORT,
-                    )
-                })
-                .or_else(|| {
-                    attrs.get(
-                        #[allow(deprecated)]
-                        semcov::attribute::NET_PEER_PORT,
-                    )
-                })
-            {
-                data.target = Some(format!("{}:{}", peer_name.as_str(), peer_port.as_str()).into());
-            } else {
-                data.target = Some(peer_name.into());
-            }
-        } else if let Some(&db_name) = attrs.get(semcov::attribute::DB_NAMESPACE).or_else(|| {
-            attrs.get(
-                #[allow(deprecated)]
-                semcov::attribute::DB_NAME,
-            )
-        }) {
-            data.target = Some(db_name.into());
-        }
-
-        if span.span_kind == SpanKind::Internal {
-            data.type_ = Some("InProc".into());
-        } else if let Some(&db_system) = attrs.get(semcov::trace::DB_SYSTEM_NAME).or_else(|| {
-            attrs.get(
-                #[allow(deprecated)]
-                semcov::attribute::DB_SYSTEM,
-            )
-        }) {
-            data.type_ = Some(db_system.into());
-        } else if let Some(&messaging_system) = attrs.get(semcov::attribute::MESSAGING_SYSTEM) {
-            data.type_ = Some(messaging_system.into());
-        } else if let Some(&rpc_system) = attrs.get(semcov::trace::RPC_SYSTEM) {
-            data.type_ = Some(rpc_system.into());
-        } else if let Some(ref properties) = data.properties {
-            if properties.keys().any(|x| x.as_ref().starts_with("http.")) {
-                data.type_ = Some("HTTP".into());
-            } else if properties.keys().any(|x| x.as_ref().starts_with("db.")) {
-                data.type_ = Some("DB".into());
-            }
+        if let (Some(&destination), Some(operation)) = (
+            attrs.get(semcov::attribute::MESSAGING_DESTINATION_NAME),
+            get_messaging_operation(&attrs),
+        ) {
+            data.name = format!("{} {}", operation.as_str(), destination.as_str()).into();
         }
 
         data
@@ -454,24 +943,12 @@ struct EventAndResource<'a>(&'a Event, Option<&'a Resource>);
 impl From<EventAndResource<'_>> for ExceptionData {
     fn from(EventAndResource(event, resource): EventAndResource<'_>) -> Self {
         let mut attrs = attrs_to_map(event.attributes.iter());
-        let exception = ExceptionDetails {
-            type_name: attrs
-                .remove(semcov::trace::EXCEPTION_TYPE)
-                .map(Into::into)
-                .unwrap_or_else(|| "<no type>".into()),
-            message: attrs
-                .remove(semcov::trace::EXCEPTION_MESSAGE)
-                .map(Into::into)
-                .unwrap_or_else(|| "<no message>".into()),
-            stack: attrs
-                .remove(semcov::trace::EXCEPTION_STACKTRACE)
-                .map(Into::into),
-        };
         ExceptionData {
             ver: 2,
-            exceptions: vec![exception],
-            severity_level: None,
+            exceptions: attrs_to_exception_details(&mut attrs, "<no type>", "<no message>"),
+            severity_level: attrs_to_escaped_severity_level(&mut attrs),
             properties: attrs_map_to_properties(attrs, resource),
+            measurements: None,
         }
     }
 }
@@ -486,6 +963,7 @@ impl From<EventAndResource<'_>> for EventData {
                 .map(Into::into)
                 .unwrap_or_else(|| "<no name>".into()),
             properties: attrs_map_to_properties(attrs, resource),
+            measurements: None,
         }
     }
 }
@@ -514,3 +992,388 @@ impl From<EventAndResource<'_>> for MessageData {
         }
     }
 }
+
+/// Span with the given kind and attributes, for unit tests.
+#[cfg(test)]
+pub(crate) fn test_span(span_kind: SpanKind, attributes: Vec<opentelemetry::KeyValue>) -> SpanData {
+    let start_time = SystemTime::UNIX_EPOCH + Duration::from_secs(10);
+    SpanData {
+        span_context: opentelemetry::trace::SpanContext::empty_context(),
+        parent_span_id: opentelemetry::trace::SpanId::INVALID,
+        span_kind,
+        name: "span".into(),
+        start_time,
+        end_time: start_time + Duration::from_secs(1),
+        attributes,
+        dropped_attributes_count: 0,
+        events: opentelemetry_sdk::trace::SpanEvents::default(),
+        links: opentelemetry_sdk::trace::SpanLinks::default(),
+        status: Status::Unset,
+        instrumentation_scope: opentelemetry::InstrumentationScope::builder("test").build(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::context_tag_keys;
+    use opentelemetry::{
+        trace::{Link, SpanContext},
+        KeyValue,
+    };
+
+    #[test]
+    fn grpc_client_dependency() {
+        let span = test_span(
+            SpanKind::Client,
+            vec![
+                KeyValue::new(semcov::trace::RPC_SYSTEM, "grpc"),
+                KeyValue::new(semcov::attribute::RPC_SERVICE, "helloworld.Greeter"),
+                KeyValue::new(semcov::attribute::RPC_METHOD, "SayHello"),
+                KeyValue::new(semcov::attribute::RPC_GRPC_STATUS_CODE, 5),
+            ],
+        );
+        let resource = Resource::builder_empty().build();
+        let data: RemoteDependencyData =
+            SpanAndResource(&span, &resource, &SuccessPolicy::default()).into();
+        assert_eq!(data.name.as_ref(), "helloworld.Greeter/SayHello");
+        assert_eq!(data.target.unwrap().as_ref(), "helloworld.Greeter");
+        assert_eq!(data.type_.unwrap().as_ref(), "GRPC");
+        assert_eq!(data.result_code.unwrap().as_ref(), "5");
+        assert_eq!(data.success, Some(false));
+    }
+
+    #[test]
+    fn grpc_server_request() {
+        let mut span = test_span(
+            SpanKind::Server,
+            vec![
+                KeyValue::new(semcov::trace::RPC_SYSTEM, "grpc"),
+                KeyValue::new(semcov::attribute::RPC_SERVICE, "helloworld.Greeter"),
+                KeyValue::new(semcov::attribute::RPC_METHOD, "SayHello"),
+                KeyValue::new(semcov::attribute::RPC_GRPC_STATUS_CODE, 5),
+            ],
+        );
+        let resource = Resource::builder_empty().build();
+        let data: RequestData = SpanAndResource(&span, &resource, &SuccessPolicy::default()).into();
+        assert_eq!(data.name.unwrap().as_ref(), "helloworld.Greeter/SayHello");
+        assert_eq!(data.response_code.as_ref(), "5");
+        assert!(data.success);
+
+        span.attributes[3] = KeyValue::new(semcov::attribute::RPC_GRPC_STATUS_CODE, 14);
+        let data: RequestData = SpanAndResource(&span, &resource, &SuccessPolicy::default()).into();
+        assert!(!data.success);
+    }
+
+    #[test]
+    fn azure_sdk_service_bus_producer_dependency() {
+        let span = test_span(
+            SpanKind::Producer,
+            vec![
+                KeyValue::new(AZ_NAMESPACE, "Microsoft.ServiceBus"),
+                KeyValue::new(semcov::attribute::MESSAGING_SYSTEM, "servicebus"),
+                KeyValue::new(semcov::attribute::MESSAGING_OPERATION_NAME, "send"),
+                KeyValue::new(semcov::attribute::MESSAGING_DESTINATION_NAME, "orders"),
+                KeyValue::new(
+                    semcov::trace::SERVER_ADDRESS,
+                    "my-namespace.servicebus.windows.net",
+                ),
+            ],
+        );
+        let resource = Resource::builder_empty().build();
+        let data: RemoteDependencyData =
+            SpanAndResource(&span, &resource, &SuccessPolicy::default()).into();
+        assert_eq!(
+            data.target.unwrap().as_ref(),
+            "my-namespace.servicebus.windows.net/orders"
+        );
+        assert_eq!(
+            data.type_.unwrap().as_ref(),
+            "Queue Message | Azure Service Bus"
+        );
+    }
+
+    #[test]
+    fn azure_sdk_storage_client_dependency() {
+        let span = test_span(
+            SpanKind::Client,
+            vec![
+                KeyValue::new(AZ_NAMESPACE, "Microsoft.Storage"),
+                KeyValue::new(semcov::trace::HTTP_REQUEST_METHOD, "GET"),
+                KeyValue::new(
+                    semcov::trace::SERVER_ADDRESS,
+                    "myaccount.blob.core.windows.net",
+                ),
+            ],
+        );
+        let resource = Resource::builder_empty().build();
+        let data: RemoteDependencyData =
+            SpanAndResource(&span, &resource, &SuccessPolicy::default()).into();
+        assert_eq!(
+            data.target.unwrap().as_ref(),
+            "myaccount.blob.core.windows.net"
+        );
+        assert_eq!(data.type_.unwrap().as_ref(), "Azure blob");
+    }
+
+    #[test]
+    fn azure_sdk_event_hubs_consumer_request() {
+        let span = test_span(
+            SpanKind::Consumer,
+            vec![
+                KeyValue::new(AZ_NAMESPACE, "Microsoft.EventHub"),
+                KeyValue::new(
+                    AZURE_SDK_PEER_ADDRESS,
+                    "sb://my-namespace.servicebus.windows.net/",
+                ),
+                KeyValue::new(AZURE_SDK_MESSAGE_BUS_DESTINATION, "my-hub"),
+            ],
+        );
+        let resource = Resource::builder_empty().build();
+        let data: RequestData = SpanAndResource(&span, &resource, &SuccessPolicy::default()).into();
+        assert_eq!(
+            data.source.unwrap().as_ref(),
+            "my-namespace.servicebus.windows.net/my-hub"
+        );
+    }
+
+    #[test]
+    fn gen_ai_client_dependency() {
+        let span = test_span(
+            SpanKind::Client,
+            vec![
+                KeyValue::new(GEN_AI_PROVIDER_NAME, "openai"),
+                KeyValue::new(semcov::attribute::GEN_AI_OPERATION_NAME, "chat"),
+                KeyValue::new(semcov::attribute::GEN_AI_REQUEST_MODEL, "gpt-4"),
+                KeyValue::new(semcov::attribute::GEN_AI_USAGE_INPUT_TOKENS, 100),
+                KeyValue::new(semcov::attribute::GEN_AI_USAGE_OUTPUT_TOKENS, 180),
+                KeyValue::new(semcov::trace::SERVER_ADDRESS, "api.openai.com"),
+                KeyValue::new(semcov::trace::HTTP_RESPONSE_STATUS_CODE, 200),
+            ],
+        );
+        let resource = Resource::builder_empty().build();
+        let mut data: RemoteDependencyData =
+            SpanAndResource(&span, &resource, &SuccessPolicy::default()).into();
+        crate::MeasurementsPolicy::Disabled.apply(
+            &attrs_to_map(span.attributes.iter()),
+            &mut data.properties,
+            &mut data.measurements,
+        );
+        assert_eq!(data.name.as_ref(), "chat gpt-4");
+        assert_eq!(data.target.unwrap().as_ref(), "api.openai.com");
+        assert_eq!(data.type_.unwrap().as_ref(), "openai");
+        let measurements = data.measurements.unwrap();
+        assert_eq!(
+            measurements.get(&semcov::attribute::GEN_AI_USAGE_INPUT_TOKENS.into()),
+            Some(&100.0)
+        );
+        assert_eq!(
+            measurements.get(&semcov::attribute::GEN_AI_USAGE_OUTPUT_TOKENS.into()),
+            Some(&180.0)
+        );
+        let properties = data.properties.unwrap();
+        assert!(!properties.contains_key(&semcov::attribute::GEN_AI_USAGE_INPUT_TOKENS.into()));
+    }
+
+    #[test]
+    fn messaging_producer_dependency() {
+        let span = test_span(
+            SpanKind::Producer,
+            vec![
+                KeyValue::new(semcov::attribute::MESSAGING_SYSTEM, "kafka"),
+                KeyValue::new(semcov::attribute::MESSAGING_OPERATION_NAME, "send"),
+                KeyValue::new(semcov::attribute::MESSAGING_DESTINATION_NAME, "orders"),
+                KeyValue::new(semcov::trace::SERVER_ADDRESS, "broker"),
+            ],
+        );
+        let resource = Resource::builder_empty().build();
+        let data: RemoteDependencyData =
+            SpanAndResource(&span, &resource, &SuccessPolicy::default()).into();
+        assert_eq!(data.name.as_ref(), "send orders");
+        assert_eq!(data.target.unwrap().as_ref(), "broker/orders");
+        assert_eq!(data.type_.unwrap().as_ref(), "Queue Message | kafka");
+    }
+
+    #[test]
+    fn messaging_consumer_request() {
+        let span = test_span(
+            SpanKind::Consumer,
+            vec![
+                KeyValue::new(semcov::attribute::MESSAGING_SYSTEM, "kafka"),
+                KeyValue::new(semcov::attribute::MESSAGING_DESTINATION_NAME, "orders"),
+                KeyValue::new(MESSAGING_MESSAGE_ENQUEUED_TIME, 9_500),
+            ],
+        );
+        let resource = Resource::builder_empty().build();
+        let data: RequestData = SpanAndResource(&span, &resource, &SuccessPolicy::default()).into();
+        assert_eq!(data.source.unwrap().as_ref(), "orders");
+        assert_eq!(
+            data.measurements
+                .unwrap()
+                .get(&MEASUREMENT_TIME_SINCE_ENQUEUED.into()),
+            Some(&500.0)
+        );
+    }
+
+    #[test]
+    fn messaging_consumer_request_enqueued_time_from_links() {
+        let mut span = test_span(SpanKind::Consumer, Vec::new());
+        for enqueued_time in [9_000, 9_800] {
+            span.links.links.push(Link::new(
+                SpanContext::empty_context(),
+                vec![KeyValue::new(AZURE_SDK_ENQUEUED_TIME, enqueued_time)],
+                0,
+            ));
+        }
+        let resource = Resource::builder_empty().build();
+        let data: RequestData = SpanAndResource(&span, &resource, &SuccessPolicy::default()).into();
+        assert_eq!(
+            data.measurements
+                .unwrap()
+                .get(&MEASUREMENT_TIME_SINCE_ENQUEUED.into()),
+            Some(&600.0)
+        );
+    }
+
+    #[test]
+    fn page_view() {
+        let span = test_span(
+            SpanKind::Internal,
+            vec![
+                KeyValue::new(PAGE_VIEW, true),
+                KeyValue::new(semcov::trace::URL_FULL, "https://example.com/home"),
+                KeyValue::new(
+                    HTTP_REQUEST_HEADER_REFERER,
+                    Value::Array(Array::String(vec!["https://example.com/".into()])),
+                ),
+            ],
+        );
+        let exporter =
+            Exporter::new_from_connection_string("InstrumentationKey=0fdcec70", ()).unwrap();
+        let resource = Resource::builder_empty().build();
+        let envelopes = exporter.create_envelopes_for_span(span, &resource);
+        assert_eq!(envelopes[0].name, "Microsoft.ApplicationInsights.PageView");
+        let data = match &envelopes[0].data {
+            Some(Data::PageView(data)) => data,
+            _ => panic!("expected page view"),
+        };
+        assert_eq!(data.name.as_ref(), "span");
+        assert_eq!(
+            data.url.as_ref().unwrap().as_ref(),
+            "https://example.com/home"
+        );
+        assert_eq!(
+            data.referrer_uri.as_ref().unwrap().as_ref(),
+            "https://example.com/"
+        );
+        assert_eq!(data.duration.as_deref(), Some("0.00:00:01.000000"));
+        assert!(!data
+            .properties
+            .as_ref()
+            .is_some_and(|x| x.contains_key(&PAGE_VIEW.into())));
+    }
+
+    #[test]
+    fn span_attributes_in_events() {
+        let mut span = test_span(
+            SpanKind::Server,
+            vec![
+                KeyValue::new("tenant", "contoso"),
+                KeyValue::new("other", "x"),
+                KeyValue::new(semcov::attribute::USER_ID, "marry"),
+            ],
+        );
+        span.events.events.push(Event::new(
+            "An event!",
+            span.start_time,
+            vec![KeyValue::new("tenant", "fabrikam")],
+            0,
+        ));
+        span.events
+            .events
+            .push(Event::new("Another event!", span.start_time, vec![], 0));
+        let exporter = Exporter::new_from_connection_string("InstrumentationKey=0fdcec70", ())
+            .unwrap()
+            .with_span_attributes_in_events(["tenant"]);
+        let resource = Resource::builder_empty().build();
+        let envelopes = exporter.create_envelopes_for_span(span, &resource);
+        let tenants: Vec<_> = envelopes[1..]
+            .iter()
+            .map(|envelope| match &envelope.data {
+                Some(Data::Message(data)) => {
+                    let properties = data.properties.as_ref().unwrap();
+                    assert_eq!(properties.len(), 1);
+                    properties
+                        .get(&"tenant".into())
+                        .unwrap()
+                        .as_ref()
+                        .to_string()
+                }
+                _ => panic!("expected message"),
+            })
+            .collect();
+        assert_eq!(tenants, vec!["fabrikam", "contoso"]);
+        for envelope in &envelopes[1..] {
+            assert_eq!(
+                envelope
+                    .tags
+                    .as_ref()
+                    .unwrap()
+                    .get(&context_tag_keys::USER_AUTH_USER_ID)
+                    .map(String::as_str),
+                Some("marry")
+            );
+        }
+    }
+
+    #[test]
+    fn user_session_and_location_tags() {
+        let attributes = vec![
+            KeyValue::new(semcov::attribute::ENDUSER_PSEUDO_ID, "anonymous"),
+            KeyValue::new(semcov::attribute::SESSION_ID, "session"),
+            KeyValue::new(semcov::trace::CLIENT_ADDRESS, "10.1.2.80"),
+        ];
+        let resource = Resource::builder_empty().build();
+        let tags = get_tags_for_span(&test_span(SpanKind::Server, attributes.clone()), &resource);
+        assert_eq!(tags.get(&context_tag_keys::USER_ID).unwrap(), "anonymous");
+        assert_eq!(tags.get(&context_tag_keys::SESSION_ID).unwrap(), "session");
+        assert_eq!(
+            tags.get(&context_tag_keys::LOCATION_IP).unwrap(),
+            "10.1.2.80"
+        );
+
+        let tags = get_tags_for_span(&test_span(SpanKind::Client, attributes), &resource);
+        assert_eq!(tags.get(&context_tag_keys::SESSION_ID).unwrap(), "session");
+        assert!(tags.get(&context_tag_keys::LOCATION_IP).is_none());
+    }
+
+    #[test]
+    fn synthetic_source() {
+        let mut span = test_span(
+            SpanKind::Server,
+            vec![KeyValue::new(
+                semcov::trace::USER_AGENT_ORIGINAL,
+                "kube-probe/1.30",
+            )],
+        );
+        span.events
+            .events
+            .push(Event::new("An event!", span.start_time, vec![], 0));
+        let exporter = Exporter::new_from_connection_string("InstrumentationKey=0fdcec70", ())
+            .unwrap()
+            .with_synthetic_source_detector(crate::SyntheticSourceDetector::new());
+        let resource = Resource::builder_empty().build();
+        let envelopes = exporter.create_envelopes_for_span(span, &resource);
+        assert_eq!(envelopes.len(), 2);
+        for envelope in envelopes {
+            assert_eq!(
+                envelope
+                    .tags
+                    .unwrap()
+                    .get(&context_tag_keys::OPERATION_SYNTHETIC_SOURCE)
+                    .map(String::as_str),
+                Some("Kubernetes Probe")
+            );
+        }
+    }
+}
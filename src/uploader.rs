@@ -1,15 +1,10 @@
-use crate::{models::Envelope, Error, HttpClient};
+use crate::{models::Envelope, Error, HttpClient, RetryNotify};
 use backon::{ExponentialBuilder, FuturesTimerSleeper, RetryableWithContext};
 use bytes::Bytes;
 use flate2::{write::GzEncoder, Compression};
 use http::{Request, Response, Uri};
 use serde::{Deserialize, Serialize};
-use std::{
-    collections::HashSet,
-    io::Write,
-    sync::{Arc, Mutex},
-    time::Duration,
-};
+use std::{collections::HashSet, io::Write, time::Duration};
 
 // We need these constants because HTTP 439 is not part of the official HTTP
 // status code registry.
@@ -52,7 +47,7 @@ pub(crate) async fn send(
     client: &dyn HttpClient,
     endpoint: &Uri,
     items: Vec<Envelope>,
-    retry_notify: Option<Arc<Mutex<dyn FnMut(&Error, Duration) + Send + 'static>>>,
+    retry_notify: Option<RetryNotify>,
 ) -> Result<(), Error> {
     let attempt = |mut items: Vec<Envelope>| async {
         match send_internal(client, endpoint, &items).await {
@@ -112,7 +107,7 @@ async fn send_internal(
     endpoint: &Uri,
     items: &[Envelope],
 ) -> Result<(), UploadError> {
-    let payload = Bytes::from(serialize_envelopes(items).map_err(|err| UploadError::Fatal(err))?);
+    let payload = Bytes::from(serialize_envelopes(items).map_err(UploadError::Fatal)?);
 
     let request = Request::post(endpoint)
         .header(http::header::CONTENT_TYPE, "application/json")
@@ -141,7 +136,7 @@ pub(crate) fn serialize_request_body(data: &[u8]) -> Result<Vec<u8>, Error> {
     // serde_json::to_writer(gzip_encoder):          247ms
     let mut gzip_encoder = GzEncoder::new(Vec::new(), Compression::default());
     gzip_encoder
-        .write_all(&data)
+        .write_all(data)
         .map_err(Error::UploadCompressRequest)?;
     gzip_encoder.finish().map_err(Error::UploadCompressRequest)
 }
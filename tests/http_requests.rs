@@ -10,18 +10,29 @@ use format::requests_to_string;
 #[cfg(feature = "live-metrics")]
 use opentelemetry::trace::{Span, Status};
 use opentelemetry::{
-    logs::{LogRecord as _, Logger as _, LoggerProvider as _, Severity},
+    logs::{AnyValue, LogRecord as _, Logger as _, LoggerProvider as _, Severity},
+    metrics::MeterProvider as _,
     trace::{
         get_active_span, mark_span_as_active, Link, SpanKind, TraceContextExt, Tracer,
         TracerProvider,
     },
     Context, KeyValue,
 };
-use opentelemetry_application_insights::{attrs as ai, Exporter};
-use opentelemetry_sdk::{logs::SdkLoggerProvider, trace::SdkTracerProvider, Resource};
+use opentelemetry_application_insights::{
+    attrs as ai, Exporter, MetricNamingPolicy, StandardMetricsSpanProcessor,
+};
+use opentelemetry_sdk::{
+    logs::SdkLoggerProvider,
+    metrics::{PeriodicReader, SdkMeterProvider, Temporality},
+    trace::SdkTracerProvider,
+    Resource,
+};
 use opentelemetry_semantic_conventions as semcov;
 use recording_client::record;
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
 use tick::{NoTick, TokioTick};
 
 // Fake instrumentation key (this is a random uuid)
@@ -239,6 +250,29 @@ fn logs() {
         record.add_attribute(semcov::trace::EXCEPTION_STACKTRACE, "A stack trace");
         logger.emit(record);
 
+        let mut record = logger.create_log_record();
+        record.set_event_name("checkout");
+        record.set_body("checked out".into());
+        record.add_attribute("cart.items", 3);
+        record.add_attribute("cart.currency", "EUR");
+        logger.emit(record);
+
+        let mut record = logger.create_log_record();
+        record.add_attribute("microsoft.custom_event.name", "signup");
+        record.add_attribute("plan", "free");
+        record.set_body(AnyValue::Map(Box::new(HashMap::from([(
+            "referrer".into(),
+            "newsletter".into(),
+        )]))));
+        logger.emit(record);
+
+        let mut record = logger.create_log_record();
+        record.set_severity_text("warning");
+        record.set_body("from severity text".into());
+        record.add_attribute(semcov::attribute::CODE_FILE_PATH, "src/main.rs");
+        record.add_attribute(semcov::attribute::CODE_LINE_NUMBER, 42);
+        logger.emit(record);
+
         tracer.in_span("span_with_logs", |_cx| {
             log::info!("with span");
         });
@@ -276,6 +310,193 @@ fn logs_with_resource_attributes_in_events_and_logs() {
     insta::assert_snapshot!(logs_with_resource_attributes_in_events_and_logs);
 }
 
+#[test]
+fn logs_with_log_body_flattening() {
+    let requests = record(NoTick, |client| {
+        let exporter = Exporter::new_from_connection_string(CONNECTION_STRING, client)
+            .expect("connection string is valid")
+            .with_log_body_flattening("body.");
+        let logger_provider = SdkLoggerProvider::builder()
+            .with_batch_exporter(exporter)
+            .build();
+
+        let logger = logger_provider.logger("test");
+        let mut record = logger.create_log_record();
+        record.set_body(AnyValue::Map(Box::new(HashMap::from([
+            ("user".into(), "marry".into()),
+            (
+                "roles".into(),
+                AnyValue::ListAny(Box::new(vec!["admin".into(), "dev".into()])),
+            ),
+        ]))));
+        record.add_attribute("body.user", "attribute wins");
+        logger.emit(record);
+
+        logger_provider.shutdown().unwrap();
+    });
+    let logs_with_log_body_flattening = requests_to_string(requests);
+    insta::assert_snapshot!(logs_with_log_body_flattening);
+}
+
+#[test]
+fn logs_with_deduplication() {
+    let requests = record(NoTick, |client| {
+        let exporter = Exporter::new_from_connection_string(CONNECTION_STRING, client)
+            .expect("connection string is valid")
+            .with_deduplication_window(Duration::from_secs(3600));
+        let logger_provider = SdkLoggerProvider::builder()
+            .with_batch_exporter(exporter)
+            .build();
+
+        let logger = logger_provider.logger("test");
+        for _ in 0..3 {
+            let mut record = logger.create_log_record();
+            record.set_severity_number(Severity::Error);
+            record.set_body("connection refused".into());
+            logger.emit(record);
+        }
+
+        // The summary of the window is uploaded on shutdown.
+        logger_provider.shutdown().unwrap();
+    });
+    let logs_with_deduplication = requests_to_string(requests);
+    insta::assert_snapshot!(logs_with_deduplication);
+}
+
+#[test]
+fn metrics_with_histogram_percentiles() {
+    let requests = record(NoTick, |client| {
+        let exporter = Exporter::new_from_connection_string(CONNECTION_STRING, client)
+            .expect("connection string is valid")
+            .with_histogram_percentiles([50.0, 95.0, 99.9]);
+        let meter_provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter).build())
+            .build();
+
+        let meter = meter_provider.meter("test");
+        let histogram = meter.f64_histogram("request.duration").build();
+        for value in 1..=100 {
+            histogram.record(value.into(), &[KeyValue::new("route", "/")]);
+        }
+
+        meter_provider.shutdown().unwrap();
+    });
+    let metrics = requests_to_string(requests);
+    insta::assert_snapshot!(metrics);
+}
+
+#[test]
+fn metrics_with_naming_policy() {
+    let requests = record(NoTick, |client| {
+        let exporter = Exporter::new_from_connection_string(CONNECTION_STRING, client)
+            .expect("connection string is valid")
+            .with_histogram_percentiles([99.9])
+            .with_metric_naming_policy(
+                MetricNamingPolicy::new()
+                    .with_scope_namespace()
+                    .with_unit_suffix(true)
+                    .with_sanitization(true),
+            );
+        let meter_provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter).build())
+            .build();
+
+        let meter = meter_provider.meter("test");
+        let histogram = meter
+            .f64_histogram("request.duration")
+            .with_unit("ms")
+            .build();
+        for value in 1..=100 {
+            histogram.record(value.into(), &[KeyValue::new("route", "/")]);
+        }
+
+        meter_provider.shutdown().unwrap();
+    });
+    let metrics = requests_to_string(requests);
+    insta::assert_snapshot!(metrics);
+}
+
+#[test]
+fn metrics_with_cumulative_temporality() {
+    let requests = record(NoTick, |client| {
+        let exporter = Exporter::new_from_connection_string(CONNECTION_STRING, client)
+            .expect("connection string is valid")
+            .with_temporality(Temporality::Cumulative);
+        let meter_provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter).build())
+            .build();
+
+        let meter = meter_provider.meter("test");
+        let counter = meter.u64_counter("requests").build();
+        counter.add(5, &[]);
+        meter_provider.force_flush().unwrap();
+        counter.add(3, &[]);
+
+        meter_provider.shutdown().unwrap();
+    });
+    let metrics = requests_to_string(requests);
+    insta::assert_snapshot!(metrics);
+}
+
+#[test]
+fn standard_metrics() {
+    use opentelemetry::trace::Span as _;
+
+    let requests = record(NoTick, |client| {
+        let exporter = Exporter::new_from_connection_string(CONNECTION_STRING, client)
+            .expect("connection string is valid");
+        let resource = Resource::builder_empty()
+            .with_attributes(vec![
+                KeyValue::new(semcov::resource::SERVICE_NAMESPACE, "test"),
+                KeyValue::new(semcov::resource::SERVICE_NAME, "server"),
+            ])
+            .build();
+        let meter_provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone()).build())
+            .with_resource(resource.clone())
+            .build();
+        let tracer_provider = SdkTracerProvider::builder()
+            .with_span_processor(StandardMetricsSpanProcessor::new(
+                &exporter,
+                &meter_provider,
+            ))
+            .with_simple_exporter(exporter)
+            .with_resource(resource)
+            .build();
+        let tracer = tracer_provider.tracer("test");
+
+        let start_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let server_span = tracer
+            .span_builder("GET /hello")
+            .with_kind(SpanKind::Server)
+            .with_start_time(start_time)
+            .with_attributes(vec![
+                KeyValue::new(semcov::trace::HTTP_REQUEST_METHOD, "GET"),
+                KeyValue::new(semcov::trace::HTTP_ROUTE, "/hello"),
+                KeyValue::new(semcov::trace::HTTP_RESPONSE_STATUS_CODE, 500),
+            ])
+            .start(&tracer);
+        let cx = Context::current_with_span(server_span);
+        let mut client_span = tracer
+            .span_builder("SELECT")
+            .with_kind(SpanKind::Client)
+            .with_start_time(start_time + Duration::from_millis(10))
+            .with_attributes(vec![
+                KeyValue::new(semcov::trace::DB_SYSTEM_NAME, "postgresql"),
+                KeyValue::new(semcov::trace::SERVER_ADDRESS, "db"),
+            ])
+            .start_with_context(&tracer, &cx);
+        client_span.end_with_timestamp(start_time + Duration::from_millis(30));
+        cx.span()
+            .end_with_timestamp(start_time + Duration::from_millis(50));
+
+        tracer_provider.shutdown().unwrap();
+        meter_provider.shutdown().unwrap();
+    });
+    let standard_metrics = requests_to_string(requests);
+    insta::assert_snapshot!(standard_metrics);
+}
+
 #[tokio::test]
 #[cfg(feature = "live-metrics")]
 async fn live_metrics() {